@@ -1,7 +1,8 @@
 use std::ops::Deref;
 use std::time::Instant;
 
-use solver::rules::DynRule;
+use solver::rules::clue::Clue;
+use solver::rules::registry::{generation_rule, rule_names};
 use solver::sudoku::{AllSolutionsContext, Difficulty, Sudoku};
 
 use crate::appstate::get_state;
@@ -17,12 +18,19 @@ pub fn generate_with_size(
     rules_src: Vec<String>,
     difficulty: String,
 ) -> Option<String> {
-    let rules = rules_src
+    let rules = match rules_src
         .iter()
         .map(Deref::deref)
-        .map(str::parse::<DynRule>)
+        .map(generation_rule)
         .collect::<Result<Vec<_>, _>>()
-        .ok()?;
+    {
+        Ok(rules) => rules,
+        Err(err) => {
+            eprintln!("Failed to make a rule: {err}");
+            eprintln!("The rules are: {}", rule_names().join(", "));
+            return None;
+        }
+    };
 
     let Ok(difficulty): Result<Difficulty, _> = difficulty.parse() else {
         eprintln!("Failed to parse difficulty: \"{difficulty}\"");
@@ -42,8 +50,7 @@ pub fn generate_with_size(
 
     let timer = Instant::now();
 
-    let Ok((mut sudoku, solved)) =
-        Sudoku::generate_with_size(size, rules, Some(progress), difficulty)
+    let Ok((sudoku, solved)) = Sudoku::generate_with_size(size, rules, Some(progress), difficulty)
     else {
         println!("Sudoku generation failed!");
         return None;
@@ -52,79 +59,19 @@ pub fn generate_with_size(
     println!("Generated in {:?}", timer.elapsed());
 
     let mut state = get_state();
-
-    state.x_positions = vec![];
-    state.parity_positions = vec![];
-    state.zipper_positions = vec![];
-    state.consecutive_positions = vec![];
-    state.thermometer_positions = vec![];
-
-    if let Some(x_rule) = sudoku.rules.iter_mut().find_map(|r| r.to_x_rule()) {
-        state.x_positions = x_rule.x_clue.clone();
-    }
-
-    if let Some(parity_rule) = sudoku.rules.iter_mut().find_map(|r| r.to_parity_rule()) {
-        state.parity_positions = parity_rule.parity_clue.clone();
-    }
-
-    if let Some(consecutive_rule) = sudoku
-        .rules
-        .iter_mut()
-        .find_map(|r| r.to_consecutive_rule())
-    {
-        state.consecutive_positions = consecutive_rule.consecutive_clue.clone();
-    }
-
-    if let Some(zipper_rule) = sudoku.rules.iter_mut().find_map(|r| r.to_zipper_rule()) {
-        state.zipper_positions = zipper_rule.zipper_clue.clone();
-    }
-
-    if let Some(thermometer_rule) = sudoku
-        .rules
-        .iter_mut()
-        .find_map(|r| r.to_thermometer_rule())
-    {
-        state.thermometer_positions = thermometer_rule
-            .themometer_clue
-            .iter()
-            .map(|ls| ls.iter().map(|u| *u as u16).collect())
-            .collect();
-    }
+    state.clues = all_clues(&sudoku);
 
     let mut str_buffer = String::new();
 
     for cell in sudoku.cells.iter() {
         match cell.available.as_slice() {
-            [value] => str_buffer.push_str(&value.to_string()),
+            [value] if cell.locked_in => str_buffer.push_str(&value.to_string()),
             _ => str_buffer.push('0'),
         }
         str_buffer.push(',');
     }
 
-    println!("");
-    for (index, rule) in sudoku.rules.iter().enumerate() {
-        if rule.print_self() {
-            if index != sudoku.rules.len() - 1 {
-                print!(" | ");
-            }
-        }
-    }
-
-    println!("\n");
-    for (index, cell) in sudoku.cells.iter().enumerate() {
-        if index % sudoku.size == 0 && index != 0 {
-            println!("");
-        }
-        if cell.available.len() == 1 {
-            print!("{}", cell.available[0]);
-        } else {
-            print!("0");
-        }
-        if index != sudoku.cells.len() - 1 {
-            print!(",");
-        }
-    }
-    println!("");
+    println!("\n{}", sudoku.to_psf());
 
     state.current_sudoku = Some((sudoku, solved));
     println!(
@@ -134,24 +81,70 @@ pub fn generate_with_size(
     Some(str_buffer)
 }
 
+// Collects the clues of all the rules, with the name of the rule they belong to
+fn all_clues(sudoku: &Sudoku) -> Vec<(&'static str, Clue)> {
+    sudoku
+        .rules
+        .iter()
+        .flat_map(|rule| rule.clues().into_iter().map(|clue| (rule.get_name(), clue)))
+        .collect()
+}
+
+// Returns the cells of the dominoes of the rule with the name
+fn domino_positions(rule_name: &str) -> Vec<(usize, usize)> {
+    get_state()
+        .clues
+        .iter()
+        .filter_map(|(name, clue)| match clue {
+            Clue::Domino { cells, .. } if *name == rule_name => Some(*cells),
+            _ => None,
+        })
+        .collect()
+}
+
+// Returns the lines of the rule with the name, leaving out the labeled lines
+fn line_positions(rule_name: &str) -> Vec<Vec<usize>> {
+    get_state()
+        .clues
+        .iter()
+        .filter_map(|(name, clue)| match clue {
+            Clue::Line { cells, label: None } if *name == rule_name => Some(cells.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
 pub fn get_x_positions() -> Vec<(usize, usize)> {
-    get_state().x_positions.clone()
+    domino_positions("XRule")
 }
 
 pub fn get_consecutive_positions() -> Vec<(usize, usize)> {
-    get_state().consecutive_positions.clone()
+    domino_positions("ConsecutiveRule")
 }
 
 pub fn get_parity_positions() -> Vec<(usize, usize)> {
-    get_state().parity_positions.clone()
+    domino_positions("ParityRule")
 }
 
+// A zipper line runs through the center, so the cells the same distance from the middle are the pairs
 pub fn get_zipper_positions() -> Vec<(usize, Vec<(usize, usize)>)> {
-    get_state().zipper_positions.clone()
+    line_positions("ZipperRule")
+        .iter()
+        .map(|cells| {
+            let middle = cells.len() / 2;
+            let arms = (1..=middle)
+                .map(|distance| (cells[middle - distance], cells[middle + distance]))
+                .collect();
+            (cells[middle], arms)
+        })
+        .collect()
 }
 
 pub fn get_thermometer_positions() -> Vec<Vec<u16>> {
-    get_state().thermometer_positions.clone()
+    line_positions("ThemometerRule")
+        .iter()
+        .map(|ther| ther.iter().map(|index| *index as u16).collect())
+        .collect()
 }
 
 pub fn check_legality(position: usize, value: u16) -> bool {
@@ -182,49 +175,7 @@ pub fn set_from_str(sudoku: String) {
     let mut solved = sudoku.clone();
     solved.solve(None, None, None).unwrap();
 
-    let mut parity = vec![];
-    let mut zippers = vec![];
-    let mut x = vec![];
-    let mut consecutive = vec![];
-    let mut thermometers = vec![];
-
-    if let Some(parity_rule) = solved.rules.iter_mut().find_map(|r| r.to_parity_rule()) {
-        parity = parity_rule.parity_clue.clone();
-    }
-
-    if let Some(zipper_rule) = solved.rules.iter_mut().find_map(|r| r.to_zipper_rule()) {
-        zippers = zipper_rule.zipper_clue.clone();
-    }
-
-    if let Some(thermometer_rule) = solved
-        .rules
-        .iter_mut()
-        .find_map(|r| r.to_thermometer_rule())
-    {
-        thermometers = thermometer_rule
-            .themometer_clue
-            .iter()
-            .map(|thermo| thermo.iter().map(|i| *i as u16).collect())
-            .collect();
-    }
-
-    if let Some(x_rule) = solved.rules.iter_mut().find_map(|r| r.to_x_rule()) {
-        x = x_rule.x_clue.clone();
-    }
-
-    if let Some(consecutive_rule) = solved
-        .rules
-        .iter_mut()
-        .find_map(|r| r.to_consecutive_rule())
-    {
-        consecutive = consecutive_rule.consecutive_clue.clone();
-    }
-
     let mut state_lock = get_state();
+    state_lock.clues = all_clues(&solved);
     state_lock.current_sudoku = Some((sudoku, solved));
-    state_lock.zipper_positions = zippers;
-    state_lock.parity_positions = parity;
-    state_lock.x_positions = x;
-    state_lock.consecutive_positions = consecutive;
-    state_lock.thermometer_positions = thermometers;
 }
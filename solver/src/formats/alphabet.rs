@@ -0,0 +1,147 @@
+// This file is the alphabets the digits of a sudoku can be written in.
+// Most published 16x16 puzzles write the digits as 0-9A-F or A-P, and 25x25 puzzles as A-Y,
+// so a digit is a single character there, and an empty cell is a dot.
+
+use std::{fmt::Display, str::FromStr};
+
+const HEX: &[u8] = b"0123456789ABCDEF";
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alphabet {
+    // The digits as numbers, where 0 is an empty cell
+    Decimal,
+    // 0-9 and A-F for the digits 1 to 16
+    Hex,
+    // A-Y for the digits 1 to 25
+    Letters,
+}
+
+impl Alphabet {
+    fn symbols(&self) -> Option<&'static [u8]> {
+        match self {
+            Alphabet::Decimal => None,
+            Alphabet::Hex => Some(HEX),
+            Alphabet::Letters => Some(LETTERS),
+        }
+    }
+
+    // The largest size where every digit has a symbol
+    pub fn max_size(&self) -> usize {
+        self.symbols().map_or(usize::MAX, |symbols| symbols.len())
+    }
+
+    // The alphabet the single character formats use for a size, when the sudoku has not chosen one
+    pub fn for_text(size: usize) -> Alphabet {
+        match size {
+            0..=9 => Alphabet::Decimal,
+            10..=16 => Alphabet::Hex,
+            _ => Alphabet::Letters,
+        }
+    }
+
+    // Writes a digit, where 0 is an empty cell
+    pub fn write_digit(&self, digit: u16) -> String {
+        match self.symbols() {
+            None => digit.to_string(),
+            Some(_) if digit == 0 => ".".to_owned(),
+            Some(symbols) => (symbols[digit as usize - 1] as char).to_string(),
+        }
+    }
+
+    // Reads a digit, where a dot is an empty cell in every alphabet, and 0 is an empty cell where it is not a digit
+    pub fn read_digit(&self, token: &str) -> Option<u16> {
+        if token == "." {
+            return Some(0);
+        }
+        match self.symbols() {
+            None => token.parse().ok(),
+            Some(symbols) => match token.as_bytes() {
+                [c] => match symbols.iter().position(|s| s == &c.to_ascii_uppercase()) {
+                    Some(index) => Some(index as u16 + 1),
+                    None if *c == b'0' => Some(0),
+                    None => None,
+                },
+                _ => None,
+            },
+        }
+    }
+
+    // Finds the alphabet of the tokens of a grid.
+    // Numbers alone are decimal, letters after F are only in the letters, and letters with digits 1-9 are hex.
+    // Otherwise, like a grid of only A-F and 0, it can be either, and None is returned.
+    pub fn detect<'a>(tokens: impl IntoIterator<Item = &'a str>) -> Option<Alphabet> {
+        let (mut digits, mut hex_letters, mut other_letters) = (false, false, false);
+        for token in tokens {
+            for c in token.chars() {
+                match c.to_ascii_uppercase() {
+                    '1'..='9' => digits = true,
+                    'A'..='F' => hex_letters = true,
+                    'G'..='Z' => other_letters = true,
+                    _ => {}
+                }
+            }
+        }
+        match (digits, hex_letters, other_letters) {
+            (_, false, false) => Some(Alphabet::Decimal),
+            (_, _, true) => Some(Alphabet::Letters),
+            (true, true, false) => Some(Alphabet::Hex),
+            (false, true, false) => None,
+        }
+    }
+}
+
+impl Display for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Alphabet::Decimal => "decimal",
+            Alphabet::Hex => "hex",
+            Alphabet::Letters => "letters",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "decimal" => Ok(Alphabet::Decimal),
+            "hex" => Ok(Alphabet::Hex),
+            "letters" => Ok(Alphabet::Letters),
+            _ => Err(format!("Unknown alphabet {s}")),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn alphabet_digit_test() {
+    assert_eq!(Alphabet::Hex.write_digit(1), "0");
+    assert_eq!(Alphabet::Hex.write_digit(16), "F");
+    assert_eq!(Alphabet::Letters.write_digit(25), "Y");
+    assert_eq!(Alphabet::Letters.write_digit(0), ".");
+    assert_eq!(Alphabet::Decimal.write_digit(12), "12");
+
+    assert_eq!(Alphabet::Hex.read_digit("0"), Some(1));
+    assert_eq!(Alphabet::Hex.read_digit("f"), Some(16));
+    assert_eq!(Alphabet::Hex.read_digit("G"), None);
+    assert_eq!(Alphabet::Letters.read_digit("0"), Some(0));
+    assert_eq!(Alphabet::Letters.read_digit("P"), Some(16));
+    assert_eq!(Alphabet::Decimal.read_digit("."), Some(0));
+
+    for alphabet in [Alphabet::Decimal, Alphabet::Hex, Alphabet::Letters] {
+        assert_eq!(alphabet.to_string().parse::<Alphabet>(), Ok(alphabet));
+    }
+}
+
+#[test]
+fn alphabet_detect_test() {
+    assert_eq!(Alphabet::detect(["1", "12", "0"]), Some(Alphabet::Decimal));
+    assert_eq!(Alphabet::detect(["A", "3", "."]), Some(Alphabet::Hex));
+    assert_eq!(Alphabet::detect(["A", "P", "0"]), Some(Alphabet::Letters));
+    assert_eq!(Alphabet::detect(["A", "F", "0"]), None);
+}
@@ -0,0 +1,262 @@
+// This file reads and writes candidate grids, where every cell is written as the digits it may still be.
+// A 9x9 is written with the candidates of a cell next to each other, like the pencil marks of a player:
+//
+// 5    3    124  | 26   7    2468 | 1489 1249 248
+//
+// Larger sizes write the candidates of a cell as a list, [10,12], which is also how Display writes them,
+// so the output of Display can be read back as well. With an alphabet the letters are next to each other again.
+
+use integer_sqrt::IntegerSquareRoot;
+
+use super::{alphabet::Alphabet, FormatError};
+use crate::{
+    rules::DynRule,
+    sudoku::{Cell, Sudoku},
+};
+
+// The candidates of a cell as they are written, either next to each other or as a list
+enum Candidates {
+    Run(Vec<char>),
+    List(Vec<String>),
+}
+
+// Reads the candidates of every cell, skipping the lines between the boxes
+fn read_candidates(s: &str) -> Result<Vec<Candidates>, FormatError> {
+    let mut cells = vec![];
+    for line in s.lines() {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && trimmed.chars().all(|c| matches!(c, '-' | '+' | ' ')) {
+            continue;
+        }
+
+        let mut chars = trimmed.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() || c == ',' || c == '|' => {}
+                '[' => {
+                    let mut list = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) => list.push(c),
+                            None => return Err(FormatError::InvalidCharacter('[')),
+                        }
+                    }
+                    let candidates = list
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|candidate| !candidate.is_empty())
+                        .map(str::to_owned)
+                        .collect();
+                    cells.push(Candidates::List(candidates));
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let mut candidates = vec![c];
+                    while let Some(c) = chars.next_if(char::is_ascii_alphanumeric) {
+                        candidates.push(c);
+                    }
+                    cells.push(Candidates::Run(candidates));
+                }
+                c => return Err(FormatError::InvalidCharacter(c)),
+            }
+        }
+    }
+    Ok(cells)
+}
+
+// Reads a single candidate in the alphabet, where an empty cell is not a candidate
+fn read_candidate(alphabet: Alphabet, candidate: &str, size: usize) -> Result<u16, FormatError> {
+    match alphabet.read_digit(candidate) {
+        Some(digit) if digit != 0 && digit as usize <= size => Ok(digit),
+        Some(digit) if alphabet == Alphabet::Decimal => Err(FormatError::InvalidCandidate(digit)),
+        _ => Err(FormatError::InvalidCharacter(
+            candidate.chars().next().unwrap_or(' '),
+        )),
+    }
+}
+
+impl Sudoku {
+    // Reads a candidate grid as a sudoku with the given rules, where every cell has exactly the candidates written.
+    // A cell with a single candidate is not locked, so solving it removes the digit from the other cells as usual.
+    pub fn from_candidates(s: &str, rules: Vec<DynRule>) -> Result<Sudoku, FormatError> {
+        let cells = read_candidates(s)?;
+        let size = cells.len().integer_sqrt();
+        let sub_s = size.integer_sqrt();
+        if size * size != cells.len() || sub_s * sub_s != size || size == 0 {
+            return Err(FormatError::InvalidSize(cells.len()));
+        }
+
+        let mut symbols = vec![];
+        for cell in &cells {
+            match cell {
+                Candidates::Run(run) => symbols.extend(run.iter().map(char::to_string)),
+                Candidates::List(list) => symbols.extend(list.iter().cloned()),
+            }
+        }
+        // A grid of 0-9A-F and nothing else could be in either alphabet, and is read as hex.
+        // Hex has no use below 16x16, so a stray letter in a small grid is an error in the decimal digits instead.
+        let alphabet = match Alphabet::detect(symbols.iter().map(String::as_str)) {
+            Some(Alphabet::Hex) if size <= 9 => Alphabet::Decimal,
+            Some(alphabet) => alphabet,
+            None => Alphabet::Hex,
+        };
+
+        // The candidates are written as a single grid, and the layout could be made for another size
+        if rules.iter().any(|r| r.layout().is_some()) {
+            return Err(FormatError::Unsupported("LayoutRule".to_owned()));
+        }
+        let mut sudoku = Sudoku::new(size, rules);
+        sudoku.validate().map_err(FormatError::InvalidClue)?;
+        if alphabet != Alphabet::Decimal {
+            sudoku.metadata.alphabet = Some(alphabet);
+        }
+        for (cell, candidates) in sudoku.cells.iter_mut().zip(cells) {
+            let mut candidates = match candidates {
+                Candidates::Run(run) => run
+                    .iter()
+                    .map(|c| read_candidate(alphabet, &c.to_string(), size))
+                    .collect::<Result<Vec<_>, _>>()?,
+                Candidates::List(list) => list
+                    .iter()
+                    .map(|c| read_candidate(alphabet, c, size))
+                    .collect::<Result<Vec<_>, _>>()?,
+            };
+            candidates.sort();
+            candidates.dedup();
+            *cell = Cell {
+                available: candidates.into(),
+                locked_in: false,
+            };
+        }
+        Ok(sudoku)
+    }
+
+    // Writes the candidates of every cell in columns, with lines between the boxes
+    pub fn to_candidates(&self) -> Result<String, FormatError> {
+        if self.layout.is_some() {
+            return Err(FormatError::Unsupported("LayoutRule".to_owned()));
+        }
+        let size = self.size;
+        let sub_s = size.integer_sqrt();
+        let alphabet = self.alphabet();
+
+        // The candidates are written next to each other when each of them is a single character
+        let words: Vec<String> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let candidates = cell.available.iter().map(|c| alphabet.write_digit(*c));
+                if (size <= 9 || alphabet != Alphabet::Decimal) && !cell.available.is_empty() {
+                    candidates.collect()
+                } else {
+                    format!("[{}]", candidates.collect::<Vec<_>>().join(","))
+                }
+            })
+            .collect();
+        let widths: Vec<usize> = (0..size)
+            .map(|x| {
+                (0..size)
+                    .map(|y| words[y * size + x].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let band = widths
+            .chunks(sub_s)
+            .map(|part| "-".repeat(part.iter().sum::<usize>() + part.len() - 1))
+            .collect::<Vec<_>>()
+            .join("-+-");
+        let mut rows = vec![];
+        for y in 0..size {
+            if y != 0 && y % sub_s == 0 {
+                rows.push(band.clone());
+            }
+            let boxes: Vec<String> = (0..size)
+                .collect::<Vec<_>>()
+                .chunks(sub_s)
+                .map(|part| {
+                    part.iter()
+                        .map(|x| format!("{:<width$}", words[y * size + x], width = widths[*x]))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            rows.push(boxes.join(" | ").trim_end().to_owned());
+        }
+        Ok(rows.join("\n"))
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn candidates_round_trip_test() {
+    let grid = "\
+12 34 | 1  234
+3  4  | 12 2
+------+-------
+4  1  | 23 23
+2  3  | 4  1";
+    let sudoku = Sudoku::from_candidates(grid, vec![]).unwrap();
+    assert_eq!(sudoku.size, 4);
+    assert_eq!(sudoku.cells[0].available.as_slice(), &[1, 2]);
+    assert_eq!(sudoku.cells[3].available.as_slice(), &[2, 3, 4]);
+    assert!(!sudoku.cells[2].locked_in);
+    assert_eq!(sudoku.to_candidates().unwrap(), grid);
+
+    // the output of Display is read as the same candidates
+    let displayed = Sudoku::from_candidates(&sudoku.to_string(), vec![]).unwrap();
+    assert_eq!(displayed.cells, sudoku.cells);
+}
+
+#[test]
+fn candidates_16x_test() {
+    let mut sudoku = Sudoku::new(16, vec![]);
+    sudoku.cells[0].available = smallvec::smallvec![10, 12];
+    sudoku.cells[1].available = smallvec::smallvec![];
+
+    let grid = sudoku.to_candidates().unwrap();
+    let words: Vec<&str> = grid.split_whitespace().take(2).collect();
+    assert_eq!(words, ["[10,12]", "[]"]);
+    let parsed = Sudoku::from_candidates(&grid, vec![]).unwrap();
+    assert_eq!(parsed.cells, sudoku.cells);
+
+    sudoku.metadata.alphabet = Some(Alphabet::Letters);
+    let grid = sudoku.to_candidates().unwrap();
+    let words: Vec<&str> = grid.split_whitespace().take(2).collect();
+    assert_eq!(words, ["JL", "[]"]);
+    let parsed = Sudoku::from_candidates(&grid, vec![]).unwrap();
+    assert_eq!(parsed.cells, sudoku.cells);
+    let displayed = Sudoku::from_candidates(&sudoku.to_string(), vec![]).unwrap();
+    assert_eq!(displayed.cells, sudoku.cells);
+}
+
+#[test]
+fn candidates_error_test() {
+    assert!(matches!(
+        Sudoku::from_candidates("1 2 3", vec![]),
+        Err(FormatError::InvalidSize(3))
+    ));
+    assert!(matches!(
+        Sudoku::from_candidates("1 2 3 4 1 2 3 4 1 2 3 4 1 2 3 45", vec![]),
+        Err(FormatError::InvalidCandidate(5))
+    ));
+    assert!(matches!(
+        Sudoku::from_candidates("1 2 3 4 1 2 3 4 1 2 3 4 1 2 3 a", vec![]),
+        Err(FormatError::InvalidCharacter('a'))
+    ));
+    // a layout of another size is refused before it makes the sudoku
+    use crate::rules::layout_rule::{Layout, LayoutRule};
+    let layout = LayoutRule::new(Layout::twodoku(9));
+    assert!(matches!(
+        Sudoku::from_candidates("1 2 3 4 1 2 3 4 1 2 3 4 1 2 3 4", vec![layout]),
+        Err(FormatError::Unsupported(_))
+    ));
+    // the clues are checked before the solver can index outside the grid
+    let x_rule = crate::rules::x_rule::XRule::new(vec![(200, 201)]);
+    assert!(matches!(
+        Sudoku::from_candidates("1 2 3 4 1 2 3 4 1 2 3 4 1 2 3 4", vec![x_rule]),
+        Err(FormatError::InvalidClue(_))
+    ));
+}
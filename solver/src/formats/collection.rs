@@ -0,0 +1,294 @@
+// This file reads and writes collections, which are files with many puzzles.
+// Every puzzle is written in PSF, which has its metadata on the lines before the rules, with a line of --- between the puzzles:
+//
+// # id: 1
+// # title: First
+// SquareRule
+//
+// 1,0,0,0,
+// ...
+// ---
+// # id: 2
+// ...
+//
+// A puzzle can also be a single line in the line format, like the 81 characters of a 9x9,
+// so files with a puzzle on each line are collections too.
+
+use std::io::{BufRead, Lines, Write};
+
+use super::{text_size, FormatError};
+use crate::{metadata::Metadata, sudoku::Sudoku};
+
+// A puzzle in the line format has only digits, letters and dots, and has a digit or dot unlike the name of a rule
+fn is_line_puzzle(s: &str) -> bool {
+    text_size(s.len()).is_ok()
+        && s.chars().all(|c| c == '.' || c.is_ascii_alphanumeric())
+        && s.chars().any(|c| c == '.' || c.is_ascii_digit())
+}
+
+// Reads the puzzles of a collection one at a time, so the whole file is never in memory
+pub struct CollectionReader<R: BufRead> {
+    lines: Lines<R>,
+    line_number: usize,
+}
+
+impl<R: BufRead> CollectionReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+        }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<Sudoku>, FormatError> {
+        let mut metadata = Metadata::default();
+        let mut psf_lines: Vec<String> = vec![];
+        let mut start = None;
+
+        for line in self.lines.by_ref() {
+            let line = line.map_err(|e| FormatError::Io(e.to_string()))?;
+            self.line_number += 1;
+            let trimmed = line.trim();
+
+            if trimmed == "---" {
+                if psf_lines.is_empty() && metadata.is_empty() {
+                    continue;
+                }
+                break;
+            }
+
+            if psf_lines.is_empty() {
+                // The metadata is read here, since a puzzle in the line format has no room for it
+                if trimmed.starts_with('#') {
+                    metadata
+                        .read_line(trimmed)
+                        .map_err(|_| FormatError::InvalidMetadata(self.line_number))?;
+                    start.get_or_insert(self.line_number);
+                    continue;
+                }
+                if trimmed.is_empty() {
+                    continue;
+                }
+
+                // The text after a puzzle in the line format is its title, as many of these files have a name there
+                let (puzzle, rest) = trimmed
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((trimmed, ""));
+                if is_line_puzzle(puzzle) {
+                    let mut sudoku = Sudoku::from_line(puzzle)
+                        .map_err(|e| FormatError::InvalidPuzzle(self.line_number, e.to_string()))?;
+                    if !rest.trim().is_empty() && metadata.title.is_none() {
+                        metadata.title = Some(rest.trim().to_owned());
+                    }
+                    sudoku.metadata = metadata;
+                    return Ok(Some(sudoku));
+                }
+            }
+
+            start.get_or_insert(self.line_number);
+            psf_lines.push(line);
+        }
+
+        let Some(start) = start else {
+            return Ok(None);
+        };
+        if psf_lines.is_empty() {
+            return Err(FormatError::InvalidPuzzle(
+                start,
+                "Metadata without a puzzle".to_owned(),
+            ));
+        }
+        // The metadata goes back in front of the rules, since the parser needs the alphabet to read the grid
+        let psf = metadata.to_psf() + psf_lines.join("\n").trim();
+        let sudoku: Sudoku = psf
+            .parse()
+            .map_err(|e| FormatError::InvalidPuzzle(start, format!("{e}")))?;
+        Ok(Some(sudoku))
+    }
+}
+
+impl<R: BufRead> Iterator for CollectionReader<R> {
+    type Item = Result<Sudoku, FormatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+// Writes puzzles to a collection one at a time
+pub struct CollectionWriter<W: Write> {
+    writer: W,
+    count: usize,
+    // Writes the puzzles in the line format instead of PSF, for the classic sudokus without rules
+    line_format: bool,
+}
+
+impl<W: Write> CollectionWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            count: 0,
+            line_format: false,
+        }
+    }
+
+    // A writer for files with a puzzle on each line, where the metadata is written on the lines before it
+    pub fn lines(writer: W) -> Self {
+        Self {
+            writer,
+            count: 0,
+            line_format: true,
+        }
+    }
+
+    pub fn write(&mut self, sudoku: &Sudoku) -> Result<(), FormatError> {
+        let io = |e: std::io::Error| FormatError::Io(e.to_string());
+        let puzzle = if self.line_format {
+            sudoku.metadata.to_psf() + &sudoku.to_line()?
+        } else {
+            sudoku.to_psf()
+        };
+
+        if self.count != 0 && !self.line_format {
+            writeln!(self.writer, "---").map_err(io)?;
+        }
+        writeln!(self.writer, "{puzzle}").map_err(io)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl Sudoku {
+    // Reads all the puzzles of a collection
+    pub fn read_collection(s: &str) -> Result<Vec<Sudoku>, FormatError> {
+        CollectionReader::new(s.as_bytes()).collect()
+    }
+
+    pub fn write_collection<'a>(
+        sudokus: impl IntoIterator<Item = &'a Sudoku>,
+    ) -> Result<String, FormatError> {
+        let mut writer = CollectionWriter::new(vec![]);
+        for sudoku in sudokus {
+            writer.write(sudoku)?;
+        }
+        Ok(String::from_utf8(writer.into_inner()).expect("The PSF is always UTF-8"))
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn collection_round_trip_test() {
+    let source = "\
+# id: 1
+# title: Small
+# author: Katinka
+# rating: Easy
+# source: tests
+# setter: Thor
+SquareRule
+
+1,0,0,0,
+0,0,0,0,
+0,0,0,0,
+0,0,0,0
+---
+XRule ;0,1 | SquareRule
+
+0,0,0,0,
+0,0,3,0,
+0,0,0,0,
+0,0,0,0
+";
+    let entries = Sudoku::read_collection(source).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].metadata.id.as_deref(), Some("1"));
+    assert_eq!(entries[0].metadata.get("setter").as_deref(), Some("Thor"));
+    assert!(entries[0].cells[0].is_single_eq(1));
+    assert!(entries[1].metadata.is_empty());
+    assert!(entries[1]
+        .rules
+        .iter()
+        .any(|rule| rule.get_name() == "XRule"));
+
+    assert_eq!(Sudoku::write_collection(&entries).unwrap(), source);
+}
+
+#[test]
+fn collection_alphabet_test() {
+    use super::alphabet::Alphabet;
+    use crate::rules::square_rule::SquareRule;
+
+    // the grids are written with the alphabet, so they can only be read with the metadata
+    for alphabet in [Alphabet::Hex, Alphabet::Letters] {
+        let mut sudoku = Sudoku::new(16, vec![SquareRule::new()]);
+        sudoku.set_cell(1, 0).unwrap();
+        sudoku.metadata.alphabet = Some(alphabet);
+        sudoku.metadata.id = Some("16".to_owned());
+
+        let source = Sudoku::write_collection([&sudoku, &sudoku]).unwrap();
+        let entries = Sudoku::read_collection(&source).unwrap();
+        assert_eq!(entries.len(), 2);
+        for entry in &entries {
+            assert_eq!(entry.metadata, sudoku.metadata);
+            assert!(entry.cells[0].is_single_eq(1));
+            assert_eq!(entry.to_psf(), sudoku.to_psf());
+        }
+    }
+}
+
+#[test]
+fn collection_lines_test() {
+    let source = "\
+53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79 Wikipedia
+# id: 2
+1...............
+
+....4...........
+";
+    let entries: Vec<_> = CollectionReader::new(source.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].metadata.title.as_deref(), Some("Wikipedia"));
+    assert_eq!(entries[1].metadata.id.as_deref(), Some("2"));
+    assert_eq!(entries[2].size, 4);
+
+    let mut writer = CollectionWriter::lines(vec![]);
+    for sudoku in &entries[1..] {
+        writer.write(sudoku).unwrap();
+    }
+    assert_eq!(writer.count(), 2);
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "# id: 2\n1...............\n....4...........\n"
+    );
+}
+
+#[test]
+fn collection_error_test() {
+    let mut reader = CollectionReader::new("1...............\n---\nNoRule\n\n0,0,0,0\n".as_bytes());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(FormatError::InvalidPuzzle(3, _)))
+    ));
+    assert!(reader.next().is_none());
+
+    assert!(matches!(
+        Sudoku::read_collection("# no colon\n1..............."),
+        Err(FormatError::InvalidMetadata(1))
+    ));
+    assert!(matches!(
+        Sudoku::read_collection("1...............\n# id: 3\n"),
+        Err(FormatError::InvalidPuzzle(2, _))
+    ));
+}
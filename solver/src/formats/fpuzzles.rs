@@ -0,0 +1,725 @@
+// This file converts sudokus to and from the JSON of the f-puzzles editor, and the share strings it makes of it.
+// Only the constraints with a matching rule are read. Any other constraint is an error instead of being dropped,
+// since the sudoku would have other solutions without it.
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use integer_sqrt::IntegerSquareRoot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{from_givens, lz_string, FormatError};
+use crate::{
+    rules::{
+        between_line_rule::BetweenLineRule,
+        consecutive_rule::ConsecutiveRule,
+        constraint_rule::{ConstraintRule, Predicate},
+        diagonal_rule::DiagonalRule,
+        difference_dot_rule::DifferenceDotRule,
+        even_odd_rule::EvenOddRule,
+        extra_regions_rule::{ExtraRegionsRule, RegionPreset},
+        knight_rule::KnightRule,
+        non_consecutive_rule::NonConsecutiveRule,
+        quadruple_rule::QuadrupleRule,
+        rule_data::RuleData,
+        square_rule::SquareRule,
+        sum_dot_rule::SumDotRule,
+        thermometer_rule::ThermometerRule,
+        x_rule::XRule,
+    },
+    sudoku::Sudoku,
+};
+
+// The keys f-puzzles uses for drawings and information, which do not change the solution
+const IGNORED: [&str; 9] = [
+    "title",
+    "author",
+    "ruleset",
+    "solution",
+    "text",
+    "line",
+    "rectangle",
+    "circle",
+    "cage",
+];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FCell {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<u16>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    given: bool,
+    // Cells with their own region make an irregular sudoku, which has no rule here
+    #[serde(default, skip_serializing)]
+    region: Option<Value>,
+}
+
+// Numbers are written as strings in most of f-puzzles, but some tools write them as numbers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum FValue {
+    Number(u64),
+    Text(String),
+}
+
+impl FValue {
+    fn number(&self) -> Result<u64, FormatError> {
+        match self {
+            FValue::Number(number) => Ok(*number),
+            FValue::Text(text) => text
+                .trim()
+                .parse()
+                .map_err(|_| FormatError::InvalidJson(format!("{text} is not a number"))),
+        }
+    }
+
+    // A number that is used as a digit or a sum, which has to fit the grid
+    fn number_in(&self, range: RangeInclusive<u16>) -> Result<u16, FormatError> {
+        let number = self.number()?;
+        u16::try_from(number)
+            .ok()
+            .filter(|number| range.contains(number))
+            .ok_or_else(|| {
+                FormatError::InvalidJson(format!(
+                    "{number} is not between {} and {}",
+                    range.start(),
+                    range.end()
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FCells {
+    cells: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<FValue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FCellMark {
+    cell: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FLines {
+    lines: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FQuadruple {
+    cells: Vec<String>,
+    values: Vec<u16>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FPuzzle {
+    size: usize,
+    grid: Vec<Vec<FCell>>,
+    #[serde(rename = "diagonal+", default, skip_serializing_if = "is_false")]
+    diagonal_positive: bool,
+    #[serde(rename = "diagonal-", default, skip_serializing_if = "is_false")]
+    diagonal_negative: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    antiknight: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    nonconsecutive: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    disjointgroups: bool,
+    // The constraints where every possible mark is given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    negative: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    thermometer: Vec<FLines>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    betweenline: Vec<FLines>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    xv: Vec<FCells>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    difference: Vec<FCells>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    killercage: Vec<FCells>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extraregion: Vec<FCells>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    evencell: Vec<FCellMark>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    oddcell: Vec<FCellMark>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    quadruple: Vec<FQuadruple>,
+    // Everything else in the puzzle, which is checked for constraints we do not support
+    #[serde(flatten)]
+    other: BTreeMap<String, Value>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+// Reads a cell name like R1C2 as an index
+fn cell_index(size: usize, name: &str) -> Result<usize, FormatError> {
+    let invalid = || FormatError::InvalidJson(format!("Invalid cell {name}"));
+    let (row, column) = name
+        .strip_prefix(['R', 'r'])
+        .and_then(|rest| rest.split_once(['C', 'c']))
+        .ok_or_else(invalid)?;
+    let row: usize = row.parse().map_err(|_| invalid())?;
+    let column: usize = column.parse().map_err(|_| invalid())?;
+    if !(1..=size).contains(&row) || !(1..=size).contains(&column) {
+        return Err(invalid());
+    }
+    Ok((row - 1) * size + column - 1)
+}
+
+fn cell_name(size: usize, index: usize) -> String {
+    format!("R{}C{}", index / size + 1, index % size + 1)
+}
+
+fn cell_indexes(size: usize, names: &[String]) -> Result<Vec<usize>, FormatError> {
+    names.iter().map(|name| cell_index(size, name)).collect()
+}
+
+fn cell_names(size: usize, indexes: &[usize]) -> Vec<String> {
+    indexes
+        .iter()
+        .map(|index| cell_name(size, *index))
+        .collect()
+}
+
+// Reads the two cells of a mark between neighbouring cells
+fn domino(size: usize, marks: &FCells) -> Result<(usize, usize), FormatError> {
+    match cell_indexes(size, &marks.cells)?.as_slice() {
+        [left, right] => Ok((*left, *right)),
+        _ => Err(FormatError::InvalidJson(format!(
+            "Expected two cells, found {:?}",
+            marks.cells
+        ))),
+    }
+}
+
+fn lines(size: usize, lines: &[FLines]) -> Result<Vec<Vec<usize>>, FormatError> {
+    lines
+        .iter()
+        .flat_map(|line| &line.lines)
+        .map(|line| cell_indexes(size, line))
+        .collect()
+}
+
+impl FPuzzle {
+    fn into_sudoku(self) -> Result<Sudoku, FormatError> {
+        let size = self.size;
+        let sub_s = size.integer_sqrt();
+        if sub_s * sub_s != size || !(4..=16).contains(&size) {
+            return Err(FormatError::InvalidSize(size * size));
+        }
+        if let Some((key, _)) = self.other.iter().find(|(key, value)| {
+            !IGNORED.contains(&key.as_str())
+                && !matches!(value, Value::Bool(false) | Value::Null)
+                && value.as_array().is_none_or(|array| !array.is_empty())
+        }) {
+            return Err(FormatError::Unsupported(key.clone()));
+        }
+
+        let mut givens = vec![0; size * size];
+        if self.grid.len() != size || self.grid.iter().any(|row| row.len() != size) {
+            return Err(FormatError::InvalidJson(format!(
+                "The grid is not {size}x{size}"
+            )));
+        }
+        for (index, cell) in self.grid.iter().flatten().enumerate() {
+            if cell.region.is_some() {
+                return Err(FormatError::Unsupported("region".to_owned()));
+            }
+            if let (Some(value), true) = (cell.value, cell.given) {
+                givens[index] = value;
+            }
+        }
+
+        let mut rules = vec![SquareRule::new()];
+        match (self.diagonal_positive, self.diagonal_negative) {
+            (true, true) => rules.push(DiagonalRule::new()),
+            (true, false) => return Err(FormatError::Unsupported("diagonal+".to_owned())),
+            (false, true) => return Err(FormatError::Unsupported("diagonal-".to_owned())),
+            (false, false) => {}
+        }
+        if self.antiknight {
+            rules.push(KnightRule::new());
+        }
+        if self.nonconsecutive {
+            rules.push(NonConsecutiveRule::new());
+        }
+
+        if !self.thermometer.is_empty() {
+            rules.push(ThermometerRule::new(
+                lines(size, &self.thermometer)?,
+                vec![],
+            ));
+        }
+        if !self.betweenline.is_empty() {
+            rules.push(BetweenLineRule::new(lines(size, &self.betweenline)?));
+        }
+
+        // An X or V, where only X marks without the negative constraint is the plain XRule
+        let xv_negative = self.negative.iter().any(|n| n == "xv");
+        if !self.xv.is_empty() || xv_negative {
+            let mut clues = vec![];
+            for mark in &self.xv {
+                let (left, right) = domino(size, mark)?;
+                let sum = match &mark.value {
+                    Some(FValue::Text(text)) if text == "X" => 10,
+                    Some(FValue::Text(text)) if text == "V" => 5,
+                    other => {
+                        return Err(FormatError::InvalidJson(format!(
+                            "Invalid xv value {other:?}"
+                        )))
+                    }
+                };
+                clues.push((left, right, sum));
+            }
+            if size == 9 && !xv_negative && clues.iter().all(|(_, _, sum)| *sum == 10) {
+                rules.push(XRule::new(
+                    clues
+                        .iter()
+                        .map(|(left, right, _)| (*left, *right))
+                        .collect(),
+                ));
+            } else {
+                rules.push(SumDotRule::xv(clues, xv_negative));
+            }
+        }
+
+        // A white kropki dot, where the difference is 1 when no value is given
+        let difference_negative = self.negative.iter().any(|n| n == "difference");
+        if !self.difference.is_empty() || difference_negative {
+            let mut clues = vec![];
+            for mark in &self.difference {
+                let (left, right) = domino(size, mark)?;
+                let difference = match &mark.value {
+                    Some(value) => value.number_in(1..=size as u16 - 1)?,
+                    None => 1,
+                };
+                clues.push((left, right, difference));
+            }
+            if !difference_negative && clues.iter().all(|(_, _, difference)| *difference == 1) {
+                rules.push(ConsecutiveRule::new(
+                    clues
+                        .iter()
+                        .map(|(left, right, _)| (*left, *right))
+                        .collect(),
+                ));
+            } else {
+                let mut differences: Vec<u16> = clues.iter().map(|(_, _, d)| *d).collect();
+                differences.push(1);
+                differences.sort();
+                differences.dedup();
+                rules.push(DifferenceDotRule::new(
+                    clues,
+                    differences,
+                    difference_negative,
+                ));
+            }
+        }
+
+        // The digits of a killer cage are all different, and add up to the value if it has one
+        if !self.killercage.is_empty() {
+            let max_sum = (1..=size as u16).sum();
+            let mut constraint_clue = vec![];
+            for cage in &self.killercage {
+                let cells = cell_indexes(size, &cage.cells)?;
+                if let Some(value) = &cage.value {
+                    let sum = value.number_in(1..=max_sum)?;
+                    constraint_clue.push((Predicate::Sum(sum), cells.clone()));
+                }
+                constraint_clue.push((Predicate::Distinct, cells));
+            }
+            rules.push(ConstraintRule::new(constraint_clue));
+        }
+
+        let extra_regions = self
+            .extraregion
+            .iter()
+            .map(|region| cell_indexes(size, &region.cells))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !extra_regions.is_empty() || self.disjointgroups {
+            let presets = if self.disjointgroups {
+                vec![RegionPreset::DisjointGroups]
+            } else {
+                vec![]
+            };
+            rules.push(ExtraRegionsRule::new(extra_regions, presets));
+        }
+
+        let mut even_odd_clue = vec![];
+        for mark in &self.evencell {
+            even_odd_clue.push((cell_index(size, &mark.cell)?, true));
+        }
+        for mark in &self.oddcell {
+            even_odd_clue.push((cell_index(size, &mark.cell)?, false));
+        }
+        if !even_odd_clue.is_empty() {
+            rules.push(EvenOddRule::new(even_odd_clue));
+        }
+
+        // The circle of a quadruple is written on its top left cell
+        if !self.quadruple.is_empty() {
+            let mut quadruple_clue = vec![];
+            for quadruple in &self.quadruple {
+                let cells = cell_indexes(size, &quadruple.cells)?;
+                let corner = *cells.iter().min().unwrap_or(&0);
+                let mut expected = vec![corner, corner + 1, corner + size, corner + size + 1];
+                let mut found = cells.clone();
+                expected.sort();
+                found.sort();
+                if found != expected || corner % size == size - 1 {
+                    return Err(FormatError::InvalidJson(format!(
+                        "The quadruple {:?} is not a 2x2 square",
+                        quadruple.cells
+                    )));
+                }
+                quadruple_clue.push((corner, quadruple.values.clone()));
+            }
+            rules.push(QuadrupleRule::new(quadruple_clue));
+        }
+
+        from_givens(size, rules, &givens)
+    }
+
+    fn from_sudoku(sudoku: &Sudoku) -> Result<FPuzzle, FormatError> {
+        let size = sudoku.size;
+        if sudoku.layout.is_some() {
+            return Err(FormatError::Unsupported("LayoutRule".to_owned()));
+        }
+
+        let mut puzzle = FPuzzle {
+            size,
+            grid: sudoku
+                .givens()
+                .chunks(size)
+                .map(|row| {
+                    row.iter()
+                        .map(|given| FCell {
+                            value: (*given != 0).then_some(*given),
+                            given: *given != 0,
+                            region: None,
+                        })
+                        .collect()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut has_boxes = false;
+        for rule in &sudoku.rules {
+            let unsupported = || FormatError::Unsupported(rule.get_name().to_owned());
+            match rule.to_data() {
+                RuleData::RowRule | RuleData::ColumnRule => {}
+                RuleData::SquareRule => has_boxes = true,
+                RuleData::KnightRule => puzzle.antiknight = true,
+                RuleData::NonConsecutiveRule => puzzle.nonconsecutive = true,
+                RuleData::DiagonalRule => {
+                    puzzle.diagonal_positive = true;
+                    puzzle.diagonal_negative = true;
+                }
+                RuleData::XRule { x_clue } => {
+                    // The XRule sums to one more than the size, which is only the X of f-puzzles on a 9x9
+                    if size != 9 {
+                        return Err(unsupported());
+                    }
+                    for (left, right) in x_clue {
+                        puzzle.xv.push(FCells {
+                            cells: cell_names(size, &[left, right]),
+                            value: Some(FValue::Text("X".to_owned())),
+                        });
+                    }
+                }
+                RuleData::SumDotRule {
+                    sum_clue,
+                    sums,
+                    negative,
+                } => {
+                    if sums.iter().any(|sum| *sum != 5 && *sum != 10) {
+                        return Err(unsupported());
+                    }
+                    for (left, right, sum) in sum_clue {
+                        let value = if sum == 10 { "X" } else { "V" };
+                        puzzle.xv.push(FCells {
+                            cells: cell_names(size, &[left, right]),
+                            value: Some(FValue::Text(value.to_owned())),
+                        });
+                    }
+                    if negative {
+                        puzzle.negative.push("xv".to_owned());
+                    }
+                }
+                RuleData::ConsecutiveRule { consecutive_clue } => {
+                    for (left, right) in consecutive_clue {
+                        puzzle.difference.push(FCells {
+                            cells: cell_names(size, &[left, right]),
+                            value: None,
+                        });
+                    }
+                }
+                RuleData::DifferenceDotRule {
+                    difference_clue,
+                    differences,
+                    negative,
+                } => {
+                    // The negative constraint of f-puzzles only forbids consecutive digits
+                    if negative && differences != [1] {
+                        return Err(unsupported());
+                    }
+                    for (left, right, difference) in difference_clue {
+                        puzzle.difference.push(FCells {
+                            cells: cell_names(size, &[left, right]),
+                            value: (difference != 1).then(|| FValue::Text(difference.to_string())),
+                        });
+                    }
+                    if negative {
+                        puzzle.negative.push("difference".to_owned());
+                    }
+                }
+                RuleData::ThermometerRule {
+                    themometer_clue,
+                    slow_themometer_clue,
+                } => {
+                    if !slow_themometer_clue.is_empty() {
+                        return Err(unsupported());
+                    }
+                    puzzle
+                        .thermometer
+                        .extend(themometer_clue.iter().map(|line| FLines {
+                            lines: vec![cell_names(size, line)],
+                        }));
+                }
+                RuleData::BetweenLineRule { between_clue } => {
+                    puzzle
+                        .betweenline
+                        .extend(between_clue.iter().map(|line| FLines {
+                            lines: vec![cell_names(size, line)],
+                        }));
+                }
+                RuleData::EvenOddRule { even_odd_clue } => {
+                    for (index, is_even) in even_odd_clue {
+                        let mark = FCellMark {
+                            cell: cell_name(size, index),
+                        };
+                        if is_even {
+                            puzzle.evencell.push(mark);
+                        } else {
+                            puzzle.oddcell.push(mark);
+                        }
+                    }
+                }
+                RuleData::QuadrupleRule { quadruple_clue } => {
+                    for (corner, values) in quadruple_clue {
+                        puzzle.quadruple.push(FQuadruple {
+                            cells: cell_names(
+                                size,
+                                &[corner, corner + 1, corner + size, corner + size + 1],
+                            ),
+                            values,
+                        });
+                    }
+                }
+                RuleData::ExtraRegionsRule {
+                    mut extra_regions_clue,
+                    presets,
+                } => {
+                    for preset in presets {
+                        match preset {
+                            RegionPreset::DisjointGroups => puzzle.disjointgroups = true,
+                            RegionPreset::Windoku => {
+                                extra_regions_clue.extend(preset.regions(size))
+                            }
+                        }
+                    }
+                    puzzle
+                        .extraregion
+                        .extend(extra_regions_clue.iter().map(|region| FCells {
+                            cells: cell_names(size, region),
+                            value: None,
+                        }));
+                }
+                // Only groups that are distinct are killer cages, and then they may have a sum
+                RuleData::ConstraintRule { constraint_clue } => {
+                    let mut cages: Vec<(Vec<usize>, Option<u16>, bool)> = vec![];
+                    for (predicate, group) in constraint_clue {
+                        let position = match cages.iter().position(|(cells, _, _)| *cells == group)
+                        {
+                            Some(position) => position,
+                            None => {
+                                cages.push((group, None, false));
+                                cages.len() - 1
+                            }
+                        };
+                        match predicate {
+                            Predicate::Sum(sum) if cages[position].1.is_none() => {
+                                cages[position].1 = Some(sum)
+                            }
+                            Predicate::Distinct => cages[position].2 = true,
+                            _ => return Err(unsupported()),
+                        }
+                    }
+                    for (cells, sum, distinct) in cages {
+                        if !distinct {
+                            return Err(unsupported());
+                        }
+                        puzzle.killercage.push(FCells {
+                            cells: cell_names(size, &cells),
+                            value: sum.map(|sum| FValue::Text(sum.to_string())),
+                        });
+                    }
+                }
+                _ => return Err(unsupported()),
+            }
+        }
+
+        // Every f-puzzles grid has boxes
+        if !has_boxes {
+            return Err(FormatError::Unsupported(
+                "a sudoku without the SquareRule".to_owned(),
+            ));
+        }
+        Ok(puzzle)
+    }
+}
+
+impl Sudoku {
+    pub fn from_fpuzzles(json: &str) -> Result<Sudoku, FormatError> {
+        let puzzle: FPuzzle =
+            serde_json::from_str(json).map_err(|e| FormatError::InvalidJson(e.to_string()))?;
+        puzzle.into_sudoku()
+    }
+
+    pub fn to_fpuzzles(&self) -> Result<String, FormatError> {
+        let puzzle = FPuzzle::from_sudoku(self)?;
+        serde_json::to_string(&puzzle).map_err(|e| FormatError::InvalidJson(e.to_string()))
+    }
+
+    // Reads the share string of f-puzzles, or a whole link with the share string after load=
+    pub fn from_fpuzzles_share(share: &str) -> Result<Sudoku, FormatError> {
+        let share = share.trim();
+        let share = match share.split_once("load=") {
+            Some((_, rest)) => rest.split('&').next().unwrap_or(rest),
+            None => share,
+        };
+        // Links that were copied around often have the + turned into a space, or encoded
+        let share = share
+            .replace("%2B", "+")
+            .replace("%2F", "/")
+            .replace("%3D", "=")
+            .replace(' ', "+");
+        let json =
+            lz_string::decompress_from_base64(&share).ok_or(FormatError::InvalidShareString)?;
+        if json.is_empty() {
+            return Err(FormatError::InvalidShareString);
+        }
+        Self::from_fpuzzles(&json)
+    }
+
+    pub fn to_fpuzzles_share(&self) -> Result<String, FormatError> {
+        Ok(lz_string::compress_to_base64(&self.to_fpuzzles()?))
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn fpuzzles_import_test() {
+    let json = r##"{
+        "size": 4,
+        "title": "Test",
+        "grid": [
+            [{"value": 1, "given": true}, {}, {}, {}],
+            [{}, {}, {}, {"value": 3}],
+            [{}, {}, {}, {}],
+            [{}, {}, {}, {}]
+        ],
+        "diagonal+": true,
+        "diagonal-": true,
+        "thermometer": [{"lines": [["R3C1", "R4C1"]], "outlineC": "#CFCFCF"}],
+        "difference": [{"cells": ["R1C2", "R1C3"]}],
+        "killercage": [{"cells": ["R3C3", "R3C4"], "value": "5"}],
+        "evencell": [{"cell": "R2C2"}],
+        "text": [{"cells": ["R1C1"], "value": "note"}],
+        "antiking": false,
+        "arrow": []
+    }"##;
+    let sudoku = Sudoku::from_fpuzzles(json).unwrap();
+    assert!(sudoku.cells[0].is_single_eq(1));
+    // a digit that is not given is left out
+    assert_eq!(sudoku.givens()[7], 0);
+    assert_eq!(
+        sudoku.to_psf(),
+        "DiagonalRule | ThermometerRule ;8,12 | ConsecutiveRule ; 1,2 | ConstraintRule ; sum 5 : 10,11 ; distinct : 10,11 | EvenOddRule ; 5,E | SquareRule\n\n\
+        1,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0"
+    );
+
+    assert!(matches!(
+        Sudoku::from_fpuzzles(&json.replace("\"arrow\": []", "\"arrow\": [{}]")),
+        Err(FormatError::Unsupported(key)) if key == "arrow"
+    ));
+    assert!(matches!(
+        Sudoku::from_fpuzzles(&json.replace("\"diagonal-\": true", "\"diagonal-\": false")),
+        Err(FormatError::Unsupported(_))
+    ));
+    assert!(matches!(
+        Sudoku::from_fpuzzles(&json.replace("R4C1", "R5C1")),
+        Err(FormatError::InvalidJson(_))
+    ));
+    // the numbers have to fit the grid, and are not cut off to a u16
+    for (from, to) in [
+        (r#"["R1C2", "R1C3"]}"#, r#"["R1C2", "R1C3"], "value": 4}"#),
+        (
+            r#"["R1C2", "R1C3"]}"#,
+            r#"["R1C2", "R1C3"], "value": 65537}"#,
+        ),
+        (r#""value": "5""#, r#""value": "0""#),
+        (r#""value": "5""#, r#""value": "11""#),
+        (r#""value": "5""#, r#""value": 65541"#),
+    ] {
+        assert!(matches!(
+            Sudoku::from_fpuzzles(&json.replace(from, to)),
+            Err(FormatError::InvalidJson(_))
+        ));
+    }
+}
+
+#[test]
+fn fpuzzles_round_trip_test() {
+    let sudoku: Sudoku = "XRule ;0,1 | ThermometerRule ;27,36,45 | ExtraRegionsRule ; disjoint | QuadrupleRule ; 60,1,2 | KnightsMove | SquareRule\n\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,4,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,0,0,9"
+        .parse()
+        .unwrap();
+
+    let json = sudoku.to_fpuzzles().unwrap();
+    assert!(json.contains(r#""xv":[{"cells":["R1C1","R1C2"],"value":"X"}]"#));
+    let parsed = Sudoku::from_fpuzzles(&json).unwrap();
+    assert_eq!(parsed.givens(), sudoku.givens());
+    assert_eq!(parsed.to_fpuzzles().unwrap(), json);
+
+    let share = sudoku.to_fpuzzles_share().unwrap();
+    let shared =
+        Sudoku::from_fpuzzles_share(&format!("https://www.f-puzzles.com/?load={share}")).unwrap();
+    assert_eq!(shared.to_fpuzzles().unwrap(), json);
+    assert!(matches!(
+        Sudoku::from_fpuzzles_share("not a puzzle"),
+        Err(FormatError::InvalidShareString | FormatError::InvalidJson(_))
+    ));
+
+    let zipper: Sudoku = "ZipperRule ;1,0+2 | SquareRule\n\n0,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0"
+        .parse()
+        .unwrap();
+    assert!(matches!(
+        zipper.to_fpuzzles(),
+        Err(FormatError::Unsupported(name)) if name == "ZipperRule"
+    ));
+}
@@ -0,0 +1,305 @@
+// This file is the LZ-String compression f-puzzles uses for its share strings, and our share codes use as well.
+// It follows compressToBase64 and decompressFromBase64 of the javascript library,
+// which work on UTF-16 code units, so the strings are converted to and from UTF-16 here.
+// The share codes write the same bits in the URL-safe base64 alphabet without padding instead.
+
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+// Only the f-puzzles share strings use the standard alphabet
+#[cfg(feature = "serde")]
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+pub const BASE64_URL: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Collects the bits of the compressed stream into characters of six bits
+struct BitWriter {
+    alphabet: &'static [u8; 64],
+    output: String,
+    value: usize,
+    position: usize,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: usize) {
+        self.value = (self.value << 1) | bit;
+        if self.position == 5 {
+            self.position = 0;
+            self.output.push(self.alphabet[self.value] as char);
+            self.value = 0;
+        } else {
+            self.position += 1;
+        }
+    }
+
+    // Writes the lowest bits of the value, starting from the least significant bit
+    fn write_bits(&mut self, mut value: usize, bits: usize) {
+        for _ in 0..bits {
+            self.write_bit(value & 1);
+            value >>= 1;
+        }
+    }
+}
+
+// The state of the compression, where the dictionary maps the strings seen so far to their codes
+struct Compressor {
+    writer: BitWriter,
+    dictionary: HashMap<Vec<u16>, usize>,
+    // The characters in the dictionary that have not been written yet
+    to_create: HashSet<Vec<u16>>,
+    enlarge_in: usize,
+    num_bits: usize,
+}
+
+impl Compressor {
+    // Every code written can grow the dictionary, so the codes need one more bit now and then
+    fn count_code(&mut self) {
+        self.enlarge_in -= 1;
+        if self.enlarge_in == 0 {
+            self.enlarge_in = 1 << self.num_bits;
+            self.num_bits += 1;
+        }
+    }
+
+    // Writes w, either as a new character or as the code it already has in the dictionary
+    fn write(&mut self, w: &Vec<u16>) {
+        if self.to_create.remove(w) {
+            if w[0] < 256 {
+                self.writer.write_bits(0, self.num_bits);
+                self.writer.write_bits(w[0] as usize, 8);
+            } else {
+                self.writer.write_bits(1, self.num_bits);
+                self.writer.write_bits(w[0] as usize, 16);
+            }
+            self.count_code();
+        } else {
+            self.writer.write_bits(self.dictionary[w], self.num_bits);
+        }
+        self.count_code();
+    }
+}
+
+// Compresses the input into characters of the alphabet, without any padding at the end
+fn compress(input: &str, alphabet: &'static [u8; 64]) -> String {
+    let mut compressor = Compressor {
+        writer: BitWriter {
+            alphabet,
+            output: String::new(),
+            value: 0,
+            position: 0,
+        },
+        dictionary: HashMap::new(),
+        to_create: HashSet::new(),
+        enlarge_in: 2,
+        num_bits: 2,
+    };
+    // The codes 0, 1 and 2 mark a new character of 8 bits, of 16 bits and the end of the stream
+    let mut dict_size = 3;
+    let mut w: Vec<u16> = vec![];
+
+    for c in input.encode_utf16() {
+        if let Entry::Vacant(entry) = compressor.dictionary.entry(vec![c]) {
+            entry.insert(dict_size);
+            dict_size += 1;
+            compressor.to_create.insert(vec![c]);
+        }
+
+        let mut wc = w.clone();
+        wc.push(c);
+        if compressor.dictionary.contains_key(&wc) {
+            w = wc;
+        } else {
+            compressor.write(&w);
+            compressor.dictionary.insert(wc, dict_size);
+            dict_size += 1;
+            w = vec![c];
+        }
+    }
+
+    if !w.is_empty() {
+        compressor.write(&w);
+    }
+
+    let mut writer = compressor.writer;
+    writer.write_bits(2, compressor.num_bits);
+
+    // Fill the last character with zeroes
+    loop {
+        writer.value <<= 1;
+        if writer.position == 5 {
+            writer.output.push(alphabet[writer.value] as char);
+            break;
+        }
+        writer.position += 1;
+    }
+    writer.output
+}
+
+#[cfg(feature = "serde")]
+pub fn compress_to_base64(input: &str) -> String {
+    let mut output = compress(input, BASE64);
+    match output.len() % 4 {
+        1 => output.push_str("==="),
+        2 => output.push_str("=="),
+        3 => output.push('='),
+        _ => {}
+    }
+    output
+}
+
+pub fn compress_to_url(input: &str) -> String {
+    compress(input, BASE64_URL)
+}
+
+// Reads the bits of the compressed stream from the characters of six bits
+struct BitReader<'a> {
+    alphabet: &'static [u8; 64],
+    input: &'a [u8],
+    index: usize,
+    value: usize,
+    position: usize,
+}
+
+impl BitReader<'_> {
+    // Characters past the end and characters outside the alphabet, like the padding, are read as zero
+    fn next_value(&mut self) -> usize {
+        let value = self
+            .input
+            .get(self.index)
+            .and_then(|c| self.alphabet.iter().position(|b| b == c))
+            .unwrap_or(0);
+        self.index += 1;
+        value
+    }
+
+    fn read_bits(&mut self, bits: usize) -> usize {
+        let mut result = 0;
+        for bit in 0..bits {
+            if self.value & self.position != 0 {
+                result |= 1 << bit;
+            }
+            self.position >>= 1;
+            if self.position == 0 {
+                self.position = 32;
+                self.value = self.next_value();
+            }
+        }
+        result
+    }
+}
+
+#[cfg(feature = "serde")]
+pub fn decompress_from_base64(input: &str) -> Option<String> {
+    decompress(input, BASE64)
+}
+
+pub fn decompress_from_url(input: &str) -> Option<String> {
+    decompress(input, BASE64_URL)
+}
+
+fn decompress(input: &str, alphabet: &'static [u8; 64]) -> Option<String> {
+    let mut reader = BitReader {
+        alphabet,
+        input: input.as_bytes(),
+        index: 0,
+        value: 0,
+        position: 32,
+    };
+    reader.value = reader.next_value();
+
+    let mut dictionary: Vec<Vec<u16>> = vec![vec![], vec![], vec![]];
+    let mut enlarge_in = 4;
+    let mut num_bits = 3;
+
+    let first = match reader.read_bits(2) {
+        0 => reader.read_bits(8) as u16,
+        1 => reader.read_bits(16) as u16,
+        _ => return Some(String::new()),
+    };
+    dictionary.push(vec![first]);
+    let mut w = vec![first];
+    let mut result = w.clone();
+
+    loop {
+        if reader.index > input.len() {
+            return None;
+        }
+
+        let mut code = reader.read_bits(num_bits);
+        match code {
+            0 | 1 => {
+                let bits = if code == 0 { 8 } else { 16 };
+                dictionary.push(vec![reader.read_bits(bits) as u16]);
+                code = dictionary.len() - 1;
+                enlarge_in -= 1;
+            }
+            2 => return String::from_utf16(&result).ok(),
+            _ => {}
+        }
+
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+
+        let entry = if code < dictionary.len() {
+            dictionary[code].clone()
+        } else if code == dictionary.len() {
+            let mut entry = w.clone();
+            entry.push(w[0]);
+            entry
+        } else {
+            return None;
+        };
+        result.extend(&entry);
+
+        let mut next = w;
+        next.push(entry[0]);
+        dictionary.push(next);
+        enlarge_in -= 1;
+        w = entry;
+
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[cfg(feature = "serde")]
+#[test]
+fn lz_string_known_test() {
+    // the output of LZString.compressToBase64 in javascript
+    for (input, compressed) in [
+        ("hello", "BYUwNmD2Q==="),
+        ("a", "IZA="),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaa", "IY18ZQ=="),
+        (
+            "ünïcödé and 数独 with surrogate 🧩",
+            "D8Ow9wxgbwJglwAgIYhgwDqaBunBB3AlgC4AWCAzgK4BOlA9gOZIECmCgfBuCXu0A===",
+        ),
+    ] {
+        assert_eq!(compress_to_base64(input), compressed);
+        assert_eq!(decompress_from_base64(compressed).unwrap(), input);
+    }
+}
+
+#[test]
+fn lz_string_round_trip_test() {
+    let long: String = (0..2000)
+        .map(|i| format!("{{\"value\":{}}},", i % 17))
+        .collect();
+    for input in ["", "b", long.as_str()] {
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            decompress_from_base64(&compress_to_base64(input)).unwrap(),
+            input
+        );
+        let compressed = compress_to_url(input);
+        assert!(compressed
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(decompress_from_url(&compressed).unwrap(), input);
+    }
+}
@@ -0,0 +1,345 @@
+// This is the module file for converting sudokus to and from the formats used outside of this project.
+// The text formats only hold the grid, so they are read as classic sudokus,
+// while the f-puzzles JSON also holds the rules the f-puzzles editor shares with us.
+// The candidate grids hold the candidates left in every cell, and the collection files hold many puzzles.
+// The share codes are our own, and hold a puzzle in a string short enough for a link.
+// The SVG is only written, as a picture of the puzzle with the overlays of its rules.
+
+use std::fmt::Display;
+
+use integer_sqrt::IntegerSquareRoot;
+
+use crate::{
+    rules::{square_rule::SquareRule, DynRule},
+    sudoku::Sudoku,
+};
+
+use self::alphabet::Alphabet;
+
+pub mod alphabet;
+pub mod candidates;
+pub mod collection;
+#[cfg(feature = "serde")]
+pub mod fpuzzles;
+mod lz_string;
+pub mod share_code;
+pub mod svg;
+
+#[derive(Debug, Clone)]
+pub enum FormatError {
+    // The number of cells is not the square of a size we can solve
+    InvalidSize(usize),
+    InvalidCharacter(char),
+    // A candidate that is not a digit of the size, like 0 or 10 in a 9x9
+    InvalidCandidate(u16),
+    // A clue of a rule that does not fit the grid, like a cell outside it
+    InvalidClue(String),
+    // Two givens break the rules, like the same digit twice in a row
+    UnsolveableError,
+    // The puzzle uses something this project has no rule for, like an arrow in f-puzzles
+    Unsupported(String),
+    InvalidJson(String),
+    InvalidShareString,
+    // A share code from a newer version of the app, with the version it starts with
+    UnsupportedVersion(char),
+    // The share code does not match its checksum, so it was changed or cut short after it was made
+    InvalidChecksum,
+    // A puzzle of a collection could not be read, with the line it starts on
+    InvalidPuzzle(usize, String),
+    // A line of metadata without the colon between the key and the value
+    InvalidMetadata(usize),
+    Io(String),
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+// Creates the sudoku and places the givens, where 0 is an empty cell
+pub(crate) fn from_givens(
+    size: usize,
+    rules: Vec<DynRule>,
+    givens: &[u16],
+) -> Result<Sudoku, FormatError> {
+    // Sudoku::new panics on a layout of another size, so it is checked first
+    if rules
+        .iter()
+        .find_map(|r| r.layout())
+        .is_some_and(|layout| layout.size != size)
+    {
+        return Err(FormatError::InvalidSize(givens.len()));
+    }
+    let mut sudoku = Sudoku::new(size, rules);
+    if givens.len() != sudoku.cells.len() {
+        return Err(FormatError::InvalidSize(givens.len()));
+    }
+    sudoku.validate().map_err(FormatError::InvalidClue)?;
+    for (index, given) in givens.iter().enumerate() {
+        if *given != 0 {
+            sudoku
+                .set_cell(*given, index)
+                .map_err(|_| FormatError::UnsolveableError)?;
+        }
+    }
+    Ok(sudoku)
+}
+
+// Returns the size of a grid with the number of cells.
+// The text formats write every digit as a single character, so the sizes above 9 use the letters of an alphabet.
+fn text_size(cell_count: usize) -> Result<usize, FormatError> {
+    let size = cell_count.integer_sqrt();
+    let sub_s = size.integer_sqrt();
+    if size * size != cell_count || sub_s * sub_s != size || !(4..=25).contains(&size) {
+        return Err(FormatError::InvalidSize(cell_count));
+    }
+    Ok(size)
+}
+
+// The alphabet of a text format above 9x9, which is the one the sudoku has chosen if every digit is a single character in it
+fn text_alphabet(sudoku: &Sudoku) -> Alphabet {
+    match sudoku.metadata.alphabet {
+        Some(alphabet) if sudoku.size > 9 && alphabet.max_size() >= sudoku.size => alphabet,
+        _ => Alphabet::for_text(sudoku.size),
+    }
+}
+
+// The text formats can not tell where the grids of a layout are
+fn text_givens(sudoku: &Sudoku) -> Result<Vec<String>, FormatError> {
+    if sudoku.layout.is_some() {
+        return Err(FormatError::Unsupported("LayoutRule".to_owned()));
+    }
+    text_size(sudoku.cells.len())?;
+    let alphabet = text_alphabet(sudoku);
+    Ok(sudoku
+        .givens()
+        .iter()
+        .map(|given| match given {
+            0 => ".".to_owned(),
+            digit => alphabet.write_digit(*digit),
+        })
+        .collect())
+}
+
+// Reads the digits of a grid, skipping whitespace and the characters in separators.
+// The blanks are the empty cells of a grid with decimal digits, while the alphabets have their own.
+fn read_grid(s: &str, separators: &[char], blanks: &[char]) -> Result<Sudoku, FormatError> {
+    let chars: Vec<char> = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && !separators.contains(c))
+        .collect();
+    let size = text_size(chars.len())?;
+
+    // A grid of 0-9A-F and nothing else could be in either alphabet, and is read as hex as most of them are
+    let alphabet = match size {
+        0..=9 => Alphabet::Decimal,
+        _ => {
+            let tokens: Vec<String> = chars.iter().map(char::to_string).collect();
+            Alphabet::detect(tokens.iter().map(String::as_str))
+                .filter(|alphabet| *alphabet != Alphabet::Decimal)
+                .unwrap_or(Alphabet::for_text(size))
+        }
+    };
+
+    let mut givens = vec![];
+    for c in chars {
+        let digit = match alphabet {
+            Alphabet::Decimal if blanks.contains(&c) => Some(0),
+            Alphabet::Decimal => c.to_digit(10).map(|digit| digit as u16),
+            _ => alphabet.read_digit(&c.to_string()),
+        };
+        match digit {
+            Some(digit) if digit as usize <= size => givens.push(digit),
+            _ => return Err(FormatError::InvalidCharacter(c)),
+        }
+    }
+
+    let mut sudoku = from_givens(size, vec![SquareRule::new()], &givens)?;
+    if alphabet != Alphabet::Decimal {
+        sudoku.metadata.alphabet = Some(alphabet);
+    }
+    Ok(sudoku)
+}
+
+// Writes the rows of the grid with a separator between the boxes.
+// The cell separator goes between the cells of a box, and the row separator is written between the bands.
+fn write_grid(
+    givens: &[String],
+    cell_separator: &str,
+    box_separator: &str,
+    row_separator: &str,
+) -> String {
+    let size = givens.len().integer_sqrt();
+    let sub_s = size.integer_sqrt();
+
+    let mut rows = vec![];
+    for (y, row) in givens.chunks(size).enumerate() {
+        if y != 0 && y % sub_s == 0 {
+            rows.push(row_separator.to_owned());
+        }
+        let boxes: Vec<String> = row
+            .chunks(sub_s)
+            .map(|part| part.join(cell_separator))
+            .collect();
+        rows.push(boxes.join(box_separator));
+    }
+    rows.join("\n")
+}
+
+impl Sudoku {
+    // Reads the single line format, like the 81 characters of a 9x9, with . or 0 for the empty cells
+    pub fn from_line(s: &str) -> Result<Sudoku, FormatError> {
+        read_grid(s.trim(), &[], &['.', '0'])
+    }
+
+    // Writes the givens on a single line with . for the empty cells
+    pub fn to_line(&self) -> Result<String, FormatError> {
+        let givens = text_givens(self)?;
+        Ok(write_grid(&givens, "", "", "").replace('\n', ""))
+    }
+
+    // Reads a grid with a line for each row, where the boxes may be separated by |, - and +
+    pub fn from_grid(s: &str) -> Result<Sudoku, FormatError> {
+        read_grid(s, &['|', '-', '+'], &['.', '0'])
+    }
+
+    // Writes a grid with spaces between the cells and lines between the boxes, like
+    // 5 3 . | . 7 . | . . .
+    pub fn to_grid(&self) -> Result<String, FormatError> {
+        let givens = text_givens(self)?;
+        let sub_s = self.size.integer_sqrt();
+        let band = vec!["-".repeat(2 * sub_s - 1); sub_s].join("-+-");
+        Ok(write_grid(&givens, " ", " | ", &band))
+    }
+
+    // Reads the .ss format of SadMan and Simple Sudoku, where the empty cells are . or X
+    pub fn from_ss(s: &str) -> Result<Sudoku, FormatError> {
+        read_grid(s, &['|', '-'], &['.', 'X', 'x'])
+    }
+
+    // Writes the .ss format, like
+    // 53.|.7.|...
+    // -----------
+    pub fn to_ss(&self) -> Result<String, FormatError> {
+        let givens = text_givens(self)?;
+        let sub_s = self.size.integer_sqrt();
+        let band = "-".repeat(self.size + sub_s - 1);
+        Ok(write_grid(&givens, "", "|", &band) + "\n")
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn line_format_test() {
+    let line = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    let sudoku = Sudoku::from_line(line).unwrap();
+    assert_eq!(sudoku.size, 9);
+    assert!(sudoku.cells[0].is_single_eq(5));
+    assert_eq!(sudoku.to_line().unwrap(), line);
+
+    let zeros = Sudoku::from_line(&line.replace('.', "0")).unwrap();
+    assert_eq!(zeros.givens(), sudoku.givens());
+
+    assert!(matches!(
+        Sudoku::from_line("123"),
+        Err(FormatError::InvalidSize(3))
+    ));
+    assert!(matches!(
+        Sudoku::from_line(&line.replace('.', "a")),
+        Err(FormatError::InvalidCharacter('a'))
+    ));
+    assert!(matches!(
+        Sudoku::from_line("11.............."),
+        Err(FormatError::UnsolveableError)
+    ));
+    use crate::rules::layout_rule::{Layout, LayoutRule};
+    let layout = LayoutRule::new(Layout::twodoku(9));
+    assert!(matches!(
+        from_givens(4, vec![layout], &[0; 16]),
+        Err(FormatError::InvalidSize(16))
+    ));
+}
+
+#[test]
+fn grid_format_test() {
+    let grid = "\
+5 3 . | . 7 . | . . .
+6 . . | 1 9 5 | . . .
+. 9 8 | . . . | . 6 .
+------+-------+------
+8 . . | . 6 . | . . 3
+4 . . | 8 . 3 | . . 1
+7 . . | . 2 . | . . 6
+------+-------+------
+. 6 . | . . . | 2 8 .
+. . . | 4 1 9 | . . 5
+. . . | . 8 . | . 7 9";
+    let sudoku = Sudoku::from_grid(grid).unwrap();
+    assert_eq!(sudoku.to_grid().unwrap(), grid);
+
+    let small = Sudoku::from_grid("1 2 | . .\n. . | 1 2\n----+----\n2 1 | . .\n. . | 2 1").unwrap();
+    assert_eq!(
+        small.to_grid().unwrap(),
+        "1 2 | . .\n. . | 1 2\n----+----\n2 1 | . .\n. . | 2 1"
+    );
+}
+
+#[test]
+fn ss_format_test() {
+    let ss = "\
+53.|.7.|...
+6..|195|...
+.98|...|.6.
+-----------
+8..|.6.|..3
+4..|8.3|..1
+7..|.2.|..6
+-----------
+.6.|...|28.
+...|419|..5
+...|.8.|.79
+";
+    let sudoku = Sudoku::from_ss(ss).unwrap();
+    assert_eq!(sudoku.to_ss().unwrap(), ss);
+    assert_eq!(
+        Sudoku::from_ss(&ss.replace('.', "X")).unwrap().givens(),
+        sudoku.givens()
+    );
+
+    let mut solved = sudoku.clone();
+    solved.solve(None, None, None).unwrap();
+    // every digit of a solved sudoku is locked, so all of them are written
+    assert!(!solved.to_ss().unwrap().contains('.'));
+}
+
+#[test]
+fn alphabet_line_test() {
+    let mut hex = "0123456789ABCDEF".to_owned();
+    hex.push_str(&".".repeat(240));
+    let sudoku = Sudoku::from_line(&hex).unwrap();
+    assert_eq!(sudoku.size, 16);
+    assert_eq!(sudoku.metadata.alphabet, Some(Alphabet::Hex));
+    assert!(sudoku.cells[0].is_single_eq(1));
+    assert!(sudoku.cells[15].is_single_eq(16));
+    assert_eq!(sudoku.to_line().unwrap(), hex);
+
+    let letters = hex.replace("0123456789ABCDEF", "ABCDEFGHIJKLMNOP");
+    let sudoku = Sudoku::from_line(&letters).unwrap();
+    assert_eq!(sudoku.metadata.alphabet, Some(Alphabet::Letters));
+    assert!(sudoku.cells[15].is_single_eq(16));
+    assert_eq!(sudoku.to_line().unwrap(), letters);
+
+    // a 16x16 without a chosen alphabet is written in hex
+    let mut sudoku = Sudoku::new(16, vec![SquareRule::new()]);
+    sudoku.set_cell(16, 1).unwrap();
+    assert!(sudoku.to_line().unwrap().starts_with(".F.."));
+    sudoku.metadata.alphabet = Some(Alphabet::Letters);
+    assert!(sudoku.to_ss().unwrap().starts_with(".P..|"));
+
+    assert!(matches!(
+        Sudoku::from_line(&letters.replace('P', "Z")),
+        Err(FormatError::InvalidCharacter('Z'))
+    ));
+}
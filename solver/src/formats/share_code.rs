@@ -0,0 +1,293 @@
+// This file is the share codes, which hold a puzzle in a string that can be sent in a link or typed into the app.
+// Every character is one of the 64 of URL-safe base64, holding six bits, and a code is written as
+//
+// version | size | cell count | givens | rules | checksum
+//
+// The version is a single digit, so the codes can change later without the old ones being read wrong.
+// The size and cell count are numbers of five bits per character, where the sixth bit tells if another follows.
+// Every given is a bit telling if the cell is empty, followed by the digit - 1 in as few bits as the size needs.
+// The rules are the PSF header of the rules compressed with LZ-String, and the checksum is the last four characters.
+// The checksum is of the puzzle as PSF, so a code that was mistyped or cut short is found before it is read.
+// The metadata is left out to keep the codes short.
+
+use super::{
+    lz_string::{self, BASE64_URL},
+    FormatError,
+};
+use crate::sudoku::Sudoku;
+
+const VERSION: char = '1';
+const CHECKSUM_LENGTH: usize = 4;
+
+// The lowest 24 bits of the FNV-1a hash of the puzzle, written as four characters
+fn checksum(psf: &str) -> String {
+    let hash = psf.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    (0..CHECKSUM_LENGTH)
+        .rev()
+        .map(|i| BASE64_URL[(hash >> (6 * i)) as usize & 63] as char)
+        .collect()
+}
+
+// The PSF the checksum is of, with the givens on a single line
+fn share_psf(header: &str, givens: &[u16]) -> String {
+    let grid = givens
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    if header.is_empty() {
+        grid
+    } else {
+        format!("{header}\n\n{grid}")
+    }
+}
+
+// The bits of a digit from 1 to size, which is written as digit - 1
+fn digit_bits(size: usize) -> usize {
+    (usize::BITS - size.saturating_sub(1).leading_zeros()) as usize
+}
+
+// Writes bits into characters, starting from the most significant bit
+struct BitWriter {
+    output: String,
+    value: usize,
+    count: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: usize, bits: usize) {
+        for bit in (0..bits).rev() {
+            self.value = (self.value << 1) | ((value >> bit) & 1);
+            self.count += 1;
+            if self.count == 6 {
+                self.output.push(BASE64_URL[self.value] as char);
+                self.value = 0;
+                self.count = 0;
+            }
+        }
+    }
+
+    // Fills the last character with zeroes, so the next part starts on a character of its own
+    fn align(&mut self) {
+        if self.count != 0 {
+            self.write(0, 6 - self.count);
+        }
+    }
+
+    fn write_number(&mut self, mut number: usize) {
+        loop {
+            let part = number & 31;
+            number >>= 5;
+            self.write(part | if number != 0 { 32 } else { 0 }, 6);
+            if number == 0 {
+                break;
+            }
+        }
+    }
+}
+
+// Reads the bits of the characters, where a character outside the alphabet is an error
+struct BitReader<'a> {
+    input: &'a [u8],
+    index: usize,
+    value: usize,
+    count: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> Result<usize, FormatError> {
+        let mut result = 0;
+        for _ in 0..bits {
+            if self.count == 0 {
+                self.value = self
+                    .input
+                    .get(self.index)
+                    .and_then(|c| BASE64_URL.iter().position(|b| b == c))
+                    .ok_or(FormatError::InvalidShareString)?;
+                self.index += 1;
+                self.count = 6;
+            }
+            self.count -= 1;
+            result = (result << 1) | ((self.value >> self.count) & 1);
+        }
+        Ok(result)
+    }
+
+    // Skips the rest of the character, as the writer filled it with zeroes
+    fn align(&mut self) {
+        self.count = 0;
+    }
+
+    fn read_number(&mut self) -> Result<usize, FormatError> {
+        let mut number = 0;
+        for shift in (0..30).step_by(5) {
+            let part = self.read(6)?;
+            number |= (part & 31) << shift;
+            if part & 32 == 0 {
+                return Ok(number);
+            }
+        }
+        Err(FormatError::InvalidShareString)
+    }
+
+    // The characters after the ones read, which are all ASCII
+    fn rest(&self) -> &str {
+        std::str::from_utf8(&self.input[self.index..]).unwrap_or_default()
+    }
+}
+
+impl Sudoku {
+    // Writes the rules and givens as a share code, which only has letters, digits, - and _
+    pub fn to_share_code(&self) -> String {
+        let header = self
+            .rules
+            .iter()
+            .filter_map(|r| r.to_psf())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let givens = self.givens();
+        let bits = digit_bits(self.size);
+
+        let mut writer = BitWriter {
+            output: VERSION.to_string(),
+            value: 0,
+            count: 0,
+        };
+        writer.write_number(self.size);
+        writer.write_number(givens.len());
+        for given in &givens {
+            match given {
+                0 => writer.write(0, 1),
+                digit => {
+                    writer.write(1, 1);
+                    writer.write(*digit as usize - 1, bits);
+                }
+            }
+        }
+        writer.align();
+
+        let mut code = writer.output;
+        code.push_str(&lz_string::compress_to_url(&header));
+        code.push_str(&checksum(&share_psf(&header, &givens)));
+        code
+    }
+
+    // Reads a share code, which is checked like any other PSF after the checksum is
+    pub fn from_share_code(code: &str) -> Result<Sudoku, FormatError> {
+        let code = code.trim();
+        match code.chars().next() {
+            Some(VERSION) => {}
+            Some(version) if version.is_ascii_digit() => {
+                return Err(FormatError::UnsupportedVersion(version))
+            }
+            _ => return Err(FormatError::InvalidShareString),
+        }
+        if code.len() <= 1 + CHECKSUM_LENGTH || !code.is_ascii() {
+            return Err(FormatError::InvalidShareString);
+        }
+        let (body, check) = code[1..].split_at(code.len() - 1 - CHECKSUM_LENGTH);
+
+        let mut reader = BitReader {
+            input: body.as_bytes(),
+            index: 0,
+            value: 0,
+            count: 0,
+        };
+        let size = reader.read_number()?;
+        let cell_count = reader.read_number()?;
+        // Every cell is at least a bit, so a broken count is found before the givens are made
+        if cell_count > body.len() * 6 {
+            return Err(FormatError::InvalidShareString);
+        }
+        let bits = digit_bits(size);
+        let mut givens = Vec::with_capacity(cell_count);
+        for _ in 0..cell_count {
+            let given = match reader.read(1)? {
+                0 => 0,
+                _ => reader.read(bits)? + 1,
+            };
+            givens.push(u16::try_from(given).map_err(|_| FormatError::InvalidShareString)?);
+        }
+        reader.align();
+
+        let header =
+            lz_string::decompress_from_url(reader.rest()).ok_or(FormatError::InvalidShareString)?;
+        let psf = share_psf(&header, &givens);
+        if checksum(&psf) != check {
+            return Err(FormatError::InvalidChecksum);
+        }
+        let sudoku = psf
+            .parse::<Sudoku>()
+            .map_err(|e| FormatError::InvalidPuzzle(e.line, e.to_string()))?;
+        if sudoku.size != size {
+            return Err(FormatError::InvalidSize(size));
+        }
+        Ok(sudoku)
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn share_code_round_trip_test() {
+    let psf = "\
+# title: Shared
+XRule ;0,1 | ThermometerRule ;8,12 ; slow 9,13 | SquareRule
+
+1,0,0,0,
+0,0,0,0,
+0,0,3,0,
+0,0,0,0";
+    let sudoku: Sudoku = psf.parse().unwrap();
+    let code = sudoku.to_share_code();
+    assert!(code.starts_with(VERSION));
+    assert!(code
+        .bytes()
+        .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+
+    let shared = Sudoku::from_share_code(&code).unwrap();
+    assert!(shared.metadata.is_empty());
+    assert_eq!(shared.givens(), sudoku.givens());
+    assert_eq!(shared.to_psf(), psf.replace("# title: Shared\n", ""));
+
+    // the code of a classic 9x9 is shorter than the line format
+    let line = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    let classic = Sudoku::from_line(line).unwrap();
+    let code = classic.to_share_code();
+    assert!(code.len() < line.len());
+    assert_eq!(
+        Sudoku::from_share_code(&code).unwrap().to_line().unwrap(),
+        line
+    );
+}
+
+#[test]
+fn share_code_error_test() {
+    let sudoku = Sudoku::from_line("1...............").unwrap();
+    let code = sudoku.to_share_code();
+    // the code of the same puzzle stays the same, as codes that are already shared must still be read
+    assert_eq!(code, "1EQgAAMoRwrghgTgpgSmANjIAmdyj");
+
+    assert!(matches!(
+        Sudoku::from_share_code(&code.replacen(VERSION, "2", 1)),
+        Err(FormatError::UnsupportedVersion('2'))
+    ));
+    assert!(matches!(
+        Sudoku::from_share_code(""),
+        Err(FormatError::InvalidShareString)
+    ));
+    assert!(matches!(
+        Sudoku::from_share_code("1a+c/e"),
+        Err(FormatError::InvalidShareString)
+    ));
+
+    // the first given changed from 1 to 2 breaks the checksum, and so does cutting the code short
+    assert!(matches!(
+        Sudoku::from_share_code(&code.replacen("EQgAA", "EQoAA", 1)),
+        Err(FormatError::InvalidChecksum)
+    ));
+    let short = format!("{}{}", &code[..code.len() - 8], &code[code.len() - 4..]);
+    assert!(Sudoku::from_share_code(&short).is_err());
+}
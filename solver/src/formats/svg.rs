@@ -0,0 +1,532 @@
+// This file draws a sudoku as SVG, for documentation, bug reports and print, without the app.
+// The overlays are drawn from the clues the rules describe, and the name of the rule picks how they look,
+// so the dominoes of the X rule are drawn as an X, while those of the consecutive rule are white dots.
+// A margin of a cell is left around the grid for the clues written outside it, like the skyscrapers.
+
+use integer_sqrt::IntegerSquareRoot;
+
+use super::FormatError;
+use crate::{
+    rules::clue::{Clue, Side},
+    sudoku::Sudoku,
+};
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    // The width and height of a cell in pixels
+    pub cell_size: usize,
+    // Draws the candidates of the cells without a digit, like pencil marks
+    pub candidates: bool,
+    // Draws the solution in the cells without a given, in blue so it is not mistaken for the givens
+    pub solution: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 50,
+            candidates: false,
+            solution: false,
+        }
+    }
+}
+
+// The colors of the extra regions, so regions next to each other can be told apart
+const REGION_COLORS: [&str; 6] = [
+    "#cfe2f3", "#fce5cd", "#d9ead3", "#ead1dc", "#fff2cc", "#d0e0e3",
+];
+const SOLUTION_COLOR: &str = "#1155cc";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// The SVG being written, where every position is in pixels
+struct Canvas {
+    svg: String,
+    width: usize,
+    cell: f64,
+}
+
+impl Canvas {
+    // The top left corner of a cell, after the margin
+    fn corner(&self, index: usize) -> (f64, f64) {
+        (
+            (index % self.width + 1) as f64 * self.cell,
+            (index / self.width + 1) as f64 * self.cell,
+        )
+    }
+
+    fn center(&self, index: usize) -> (f64, f64) {
+        let (x, y) = self.corner(index);
+        (x + self.cell / 2.0, y + self.cell / 2.0)
+    }
+
+    fn rect(&mut self, (x, y): (f64, f64), width: f64, height: f64, style: &str) {
+        self.svg.push_str(&format!(
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" {style}/>\n"
+        ));
+    }
+
+    fn circle(&mut self, (x, y): (f64, f64), radius: f64, style: &str) {
+        self.svg.push_str(&format!(
+            "<circle cx=\"{x}\" cy=\"{y}\" r=\"{radius}\" {style}/>\n"
+        ));
+    }
+
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), style: &str) {
+        self.svg.push_str(&format!(
+            "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" {style}/>\n"
+        ));
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], style: &str) {
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+        self.svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke-linecap=\"round\" stroke-linejoin=\"round\" {style}/>\n",
+            points.join(" ")
+        ));
+    }
+
+    // Writes the text centered on the position
+    fn text(&mut self, (x, y): (f64, f64), size: f64, text: &str, style: &str) {
+        self.svg.push_str(&format!(
+            "<text x=\"{x}\" y=\"{y}\" font-size=\"{size}\" text-anchor=\"middle\" dominant-baseline=\"central\"{}>{}</text>\n",
+            if style.is_empty() { String::new() } else { format!(" {style}") },
+            escape(text)
+        ));
+    }
+
+    // The dashed border of a cage, drawn a little inside the cells.
+    // A side is drawn where the next cell is not in the cage, and runs all the way to the cell next to it in the cage,
+    // or past it into the inner corner when the cage turns back towards the side.
+    fn cage(&mut self, cells: &[usize], height: usize) {
+        let inset = self.cell * 0.08;
+        let width = self.width;
+        let has = |x: isize, y: isize| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < width
+                && (y as usize) < height
+                && cells.contains(&(y as usize * width + x as usize))
+        };
+        let style = "stroke=\"black\" stroke-width=\"1\" stroke-dasharray=\"4,3\"";
+
+        for index in cells {
+            let (left, top) = self.corner(*index);
+            let (right, bottom) = (left + self.cell, top + self.cell);
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            let start = |neighbour: bool, turn: bool, edge: f64| match (neighbour, turn) {
+                (false, _) => edge + inset,
+                (true, false) => edge,
+                (true, true) => edge - inset,
+            };
+            let end = |neighbour: bool, turn: bool, edge: f64| match (neighbour, turn) {
+                (false, _) => edge - inset,
+                (true, false) => edge,
+                (true, true) => edge + inset,
+            };
+
+            if !has(x, y - 1) {
+                let from = (start(has(x - 1, y), has(x - 1, y - 1), left), top + inset);
+                let to = (end(has(x + 1, y), has(x + 1, y - 1), right), top + inset);
+                self.line(from, to, style);
+            }
+            if !has(x, y + 1) {
+                let from = (
+                    start(has(x - 1, y), has(x - 1, y + 1), left),
+                    bottom - inset,
+                );
+                let to = (end(has(x + 1, y), has(x + 1, y + 1), right), bottom - inset);
+                self.line(from, to, style);
+            }
+            if !has(x - 1, y) {
+                let from = (left + inset, start(has(x, y - 1), has(x - 1, y - 1), top));
+                let to = (left + inset, end(has(x, y + 1), has(x - 1, y + 1), bottom));
+                self.line(from, to, style);
+            }
+            if !has(x + 1, y) {
+                let from = (right - inset, start(has(x, y - 1), has(x + 1, y - 1), top));
+                let to = (right - inset, end(has(x, y + 1), has(x + 1, y + 1), bottom));
+                self.line(from, to, style);
+            }
+        }
+    }
+}
+
+impl Sudoku {
+    // Every digit of the solution, from the metadata when it is there, and found by solving a copy otherwise
+    fn solution_digits(&self) -> Result<Vec<u16>, FormatError> {
+        if let Some(solution) = &self.metadata.solution {
+            if solution.len() == self.cells.len() {
+                return Ok(solution.clone());
+            }
+        }
+        let mut solved = self.clone();
+        solved
+            .solve(None, None, None)
+            .map_err(|_| FormatError::UnsolveableError)?;
+        Ok(solved
+            .cells
+            .iter()
+            .map(|cell| cell.available.first().copied().unwrap_or(0))
+            .collect())
+    }
+
+    // Draws the sudoku with the overlays of every rule.
+    // Only drawing the solution can fail, when the sudoku has none.
+    pub fn to_svg(&self, options: &SvgOptions) -> Result<String, FormatError> {
+        let width = self.width();
+        let height = self.cells.len() / width;
+        let cell = options.cell_size as f64;
+        let solution = match options.solution {
+            true => Some(self.solution_digits()?),
+            false => None,
+        };
+        let alphabet = self.alphabet();
+
+        let (pixel_width, pixel_height) = ((width + 2) as f64 * cell, (height + 2) as f64 * cell);
+        let mut canvas = Canvas {
+            svg: format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{pixel_width}\" height=\"{pixel_height}\" viewBox=\"0 0 {pixel_width} {pixel_height}\" font-family=\"sans-serif\">\n"
+            ),
+            width,
+            cell,
+        };
+        if let Some(title) = &self.metadata.title {
+            canvas
+                .svg
+                .push_str(&format!("<title>{}</title>\n", escape(title)));
+        }
+        canvas.rect((0.0, 0.0), pixel_width, pixel_height, "fill=\"white\"");
+
+        let clues: Vec<(&str, Clue)> = self
+            .rules
+            .iter()
+            .flat_map(|rule| {
+                rule.clues()
+                    .into_iter()
+                    .map(move |clue| (rule.get_name(), clue))
+            })
+            .collect();
+
+        // The shading goes below the lines of the grid
+        let mut region_count = 0;
+        for (_, clue) in &clues {
+            match clue {
+                Clue::Region { cells } => {
+                    let fill = format!(
+                        "fill=\"{}\"",
+                        REGION_COLORS[region_count % REGION_COLORS.len()]
+                    );
+                    for index in cells {
+                        canvas.rect(canvas.corner(*index), cell, cell, &fill);
+                    }
+                    region_count += 1;
+                }
+                Clue::Cell { index, label } => {
+                    let center = canvas.center(*index);
+                    match *label {
+                        "E" => {
+                            let (x, y) = canvas.corner(*index);
+                            let inset = cell * 0.12;
+                            let side = cell - 2.0 * inset;
+                            canvas.rect((x + inset, y + inset), side, side, "fill=\"#d9d9d9\"");
+                        }
+                        "O" => canvas.circle(center, cell * 0.38, "fill=\"#d9d9d9\""),
+                        label => {
+                            let bottom = (center.0, center.1 + cell * 0.32);
+                            canvas.text(bottom, cell * 0.2, label, "fill=\"#666666\"");
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for index in (0..self.cells.len()).filter(|index| self.is_active(*index)) {
+            canvas.rect(
+                canvas.corner(index),
+                cell,
+                cell,
+                "fill=\"none\" stroke=\"#999999\" stroke-width=\"1\"",
+            );
+        }
+
+        if self.layout.is_none() && self.rules.iter().any(|r| r.get_name() == "DiagonalRule") {
+            let style = "stroke=\"#a4c2f4\" stroke-width=\"2\"";
+            let (near, far) = (cell, (width + 1) as f64 * cell);
+            canvas.line((near, near), (far, far), style);
+            canvas.line((far, near), (near, far), style);
+        }
+
+        for (name, clue) in &clues {
+            let Clue::Line { cells, label } = clue else {
+                continue;
+            };
+            let points: Vec<(f64, f64)> = cells.iter().map(|index| canvas.center(*index)).collect();
+            match *name {
+                "ThemometerRule" => {
+                    let color = match label {
+                        Some("slow") => "#a4c2f4",
+                        _ => "#c0c0c0",
+                    };
+                    let style = format!("stroke=\"{color}\" stroke-width=\"{}\"", cell * 0.3);
+                    canvas.polyline(&points, &style);
+                    if let Some(bulb) = points.first() {
+                        canvas.circle(*bulb, cell * 0.38, &format!("fill=\"{color}\""));
+                    }
+                }
+                "ZipperRule" => {
+                    let style = format!("stroke=\"#c9a0ff\" stroke-width=\"{}\"", cell * 0.15);
+                    canvas.polyline(&points, &style);
+                }
+                "BetweenLineRule" => {
+                    let style = format!("stroke=\"#999999\" stroke-width=\"{}\"", cell * 0.08);
+                    canvas.polyline(&points, &style);
+                    for end in [points.first(), points.last()].into_iter().flatten() {
+                        canvas.circle(
+                            *end,
+                            cell * 0.4,
+                            "fill=\"white\" stroke=\"#999999\" stroke-width=\"2\"",
+                        );
+                    }
+                }
+                _ => {
+                    let style = format!("stroke=\"#6fa8dc\" stroke-width=\"{}\"", cell * 0.1);
+                    canvas.polyline(&points, &style);
+                }
+            }
+        }
+
+        // Every grid has a thick border, and the boxes are drawn when the grid has them
+        let sub_s = self.size.integer_sqrt();
+        let has_boxes =
+            self.layout.is_some() || self.rules.iter().any(|r| r.get_name() == "SquareRule");
+        let grids = match &self.layout {
+            Some(layout) => layout.grids.clone(),
+            None => vec![(0, 0)],
+        };
+        for (grid_x, grid_y) in grids {
+            let origin = canvas.corner(grid_y * width + grid_x);
+            if has_boxes && sub_s > 0 {
+                let side = sub_s as f64 * cell;
+                for box_index in 0..self.size {
+                    let x = origin.0 + (box_index % sub_s) as f64 * side;
+                    let y = origin.1 + (box_index / sub_s) as f64 * side;
+                    canvas.rect(
+                        (x, y),
+                        side,
+                        side,
+                        "fill=\"none\" stroke=\"black\" stroke-width=\"2\"",
+                    );
+                }
+            }
+            let side = self.size as f64 * cell;
+            canvas.rect(
+                origin,
+                side,
+                side,
+                "fill=\"none\" stroke=\"black\" stroke-width=\"3\"",
+            );
+        }
+
+        for (name, clue) in &clues {
+            match clue {
+                Clue::Cage { cells, label } => {
+                    canvas.cage(cells, height);
+                    if let Some(first) = cells.iter().min() {
+                        let (x, y) = canvas.corner(*first);
+                        let position = (x + cell * 0.1, y + cell * 0.1 + cell * 0.09);
+                        canvas.svg.push_str(&format!(
+                            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" dominant-baseline=\"central\" paint-order=\"stroke\" stroke=\"white\" stroke-width=\"3\">{}</text>\n",
+                            position.0,
+                            position.1,
+                            cell * 0.2,
+                            escape(label)
+                        ));
+                    }
+                }
+                Clue::Domino {
+                    cells: (first, second),
+                    value,
+                } => {
+                    let (a, b) = (canvas.center(*first), canvas.center(*second));
+                    let middle = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                    let dot = |fill: &str| {
+                        format!("fill=\"{fill}\" stroke=\"black\" stroke-width=\"1.5\"")
+                    };
+                    match *name {
+                        "XRule" => canvas.text(middle, cell * 0.4, "X", "font-weight=\"bold\" paint-order=\"stroke\" stroke=\"white\" stroke-width=\"3\""),
+                        "ParityRule" => canvas.circle(middle, cell * 0.12, &dot("#999999")),
+                        "ConsecutiveRule" => canvas.circle(middle, cell * 0.12, &dot("white")),
+                        // The first cell of an inequality has the smaller digit, and the sign points at it
+                        "InequalityRule" => {
+                            let sign = match (*first % width == *second % width, first < second) {
+                                (false, true) => "<",
+                                (false, false) => ">",
+                                (true, true) => "∧",
+                                (true, false) => "∨",
+                            };
+                            canvas.text(middle, cell * 0.35, sign, "font-weight=\"bold\" paint-order=\"stroke\" stroke=\"white\" stroke-width=\"3\"");
+                        }
+                        _ => {
+                            canvas.circle(middle, cell * 0.15, &dot("white"));
+                            if let Some(value) = value {
+                                canvas.text(middle, cell * 0.18, &value.to_string(), "");
+                            }
+                        }
+                    }
+                }
+                Clue::Corner { index, digits } => {
+                    let (x, y) = canvas.corner(*index);
+                    let point = (x + cell, y + cell);
+                    canvas.circle(
+                        point,
+                        cell * 0.25,
+                        "fill=\"white\" stroke=\"black\" stroke-width=\"1.5\"",
+                    );
+                    let digits: Vec<String> = digits.iter().map(u16::to_string).collect();
+                    canvas.text(point, cell * 0.16, &digits.join(""), "");
+                }
+                Clue::Outside {
+                    side,
+                    position,
+                    value,
+                } => {
+                    let (x, y) = match side {
+                        Side::Top => (*position + 1, 0),
+                        Side::Bottom => (*position + 1, height + 1),
+                        Side::Left => (0, *position + 1),
+                        Side::Right => (width + 1, *position + 1),
+                    };
+                    let center = ((x as f64 + 0.5) * cell, (y as f64 + 0.5) * cell);
+                    canvas.text(center, cell * 0.45, &value.to_string(), "");
+                }
+                _ => {}
+            }
+        }
+
+        // The digits go on top of everything, so the overlays never hide them
+        for (index, sudoku_cell) in self.cells.iter().enumerate() {
+            if !self.is_active(index) {
+                continue;
+            }
+            let center = canvas.center(index);
+            if sudoku_cell.locked_in {
+                let digit = alphabet.write_digit(sudoku_cell.available[0]);
+                canvas.text(center, cell * 0.6, &digit, "");
+            } else if let Some(solution) = &solution {
+                if solution[index] != 0 {
+                    let digit = alphabet.write_digit(solution[index]);
+                    let style = format!("fill=\"{SOLUTION_COLOR}\"");
+                    canvas.text(center, cell * 0.6, &digit, &style);
+                }
+            } else if options.candidates && sub_s > 0 {
+                // Every candidate has its own place in a small grid inside the cell
+                let (x, y) = canvas.corner(index);
+                let step = cell / sub_s as f64;
+                for candidate in &sudoku_cell.available {
+                    let place = *candidate as usize - 1;
+                    let position = (
+                        x + (place % sub_s) as f64 * step + step / 2.0,
+                        y + (place / sub_s) as f64 * step + step / 2.0,
+                    );
+                    let digit = alphabet.write_digit(*candidate);
+                    canvas.text(position, step * 0.75, &digit, "fill=\"#666666\"");
+                }
+            }
+        }
+
+        canvas.svg.push_str("</svg>\n");
+        Ok(canvas.svg)
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn svg_overlays_test() {
+    let sudoku: Sudoku = "\
+# title: Overlays & more
+XRule ;0,1 | ConsecutiveRule ;2,3 | ParityRule ;9,18 | ThermometerRule ;30,31,32 ; slow 40,41 | \
+ZipperRule ;58,57+59 | DiagonalRule | ConstraintRule ; sum 10 : 60,61,70 | QuadrupleRule ;4,1,2 | \
+EvenOddRule ;80,E ; 79,O | InequalityRule ;27,36 | SquareRule
+
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,5,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0,
+0,0,0,0,0,0,0,0,0"
+        .parse()
+        .unwrap();
+
+    let svg = sudoku.to_svg(&SvgOptions::default()).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"550\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("<title>Overlays &amp; more</title>"));
+    // the single given is the only large digit
+    assert_eq!(svg.matches("font-size=\"30\"").count(), 1);
+    assert!(svg.contains(">X</text>"));
+    assert!(svg.contains(">∧</text>"));
+    assert!(svg.contains(">sum 10</text>"));
+    assert!(svg.contains(">12</text>"));
+    assert!(svg.contains("stroke-dasharray"));
+    // two thermometers and a zipper
+    assert_eq!(svg.matches("<polyline").count(), 3);
+    // the diagonals, the 81 cells, the 9 boxes and the border
+    assert!(svg.matches("stroke=\"#a4c2f4\" stroke-width=\"2\"").count() == 2);
+    assert_eq!(
+        svg.matches("stroke=\"#999999\" stroke-width=\"1\"").count(),
+        81
+    );
+    assert_eq!(svg.matches("stroke-width=\"2\"/>").count(), 9 + 2);
+}
+
+#[test]
+fn svg_digits_test() {
+    let mut sudoku = Sudoku::from_line("1...............").unwrap();
+    let svg = sudoku.to_svg(&SvgOptions::default()).unwrap();
+    assert_eq!(svg.matches("</text>").count(), 1);
+
+    let candidates = SvgOptions {
+        candidates: true,
+        ..SvgOptions::default()
+    };
+    let svg = sudoku.to_svg(&candidates).unwrap();
+    let candidate_count: usize = sudoku.cells[1..].iter().map(|c| c.available.len()).sum();
+    assert_eq!(svg.matches("</text>").count(), 1 + candidate_count);
+
+    let solution = SvgOptions {
+        solution: true,
+        ..SvgOptions::default()
+    };
+    let svg = sudoku.to_svg(&solution).unwrap();
+    assert_eq!(svg.matches(SOLUTION_COLOR).count(), 15);
+
+    sudoku.metadata.solution = Some(vec![1, 2, 3, 4, 3, 4, 1, 2, 2, 1, 4, 3, 4, 3, 2, 1]);
+    let svg = sudoku.to_svg(&solution).unwrap();
+    assert!(svg.contains(&format!("fill=\"{SOLUTION_COLOR}\">2</text>")));
+
+    // a samurai draws the five grids and leaves out the cells between them
+    let samurai: Sudoku = format!("LayoutRule ; 9 ; samurai\n\n{}", vec!["0"; 441].join(","))
+        .parse()
+        .unwrap();
+    let svg = samurai.to_svg(&SvgOptions::default()).unwrap();
+    let active = (0..samurai.cells.len())
+        .filter(|i| samurai.is_active(*i))
+        .count();
+    assert!(active < 441);
+    assert_eq!(
+        svg.matches("stroke=\"#999999\" stroke-width=\"1\"").count(),
+        active
+    );
+    assert_eq!(svg.matches("stroke-width=\"3\"/>").count(), 5);
+}
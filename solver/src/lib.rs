@@ -1,3 +1,5 @@
 // Author Thor s224817
+pub mod formats;
+pub mod metadata;
 pub mod sudoku;
 pub mod rules;
\ No newline at end of file
@@ -6,11 +6,19 @@ use std::{
     time::Instant,
 };
 
-use rules::square_rule::SquareRule;
+use rules::{
+    registry::{generation_rule, rule_names},
+    square_rule::SquareRule,
+};
 use sudoku::Sudoku;
 
-use crate::sudoku::Difficulty;
+use crate::{
+    formats::{alphabet::Alphabet, svg::SvgOptions},
+    sudoku::Difficulty,
+};
 
+pub mod formats;
+pub mod metadata;
 pub mod rules;
 pub mod sudoku;
 
@@ -32,21 +40,31 @@ fn main() {
             Difficulty::Medium
         };
 
-        let (sudoku, _) =
-            Sudoku::generate_with_size(9, vec![SquareRule::new()], None, difficulty).unwrap();
-
-        println!("SquareRule\n");
-
-        for (index, cell) in sudoku.cells.iter().enumerate() {
-            match cell.available.as_slice() {
-                [value] => print!("{value}"),
-                _ => print!("0"),
-            }
-            if index + 1 < sudoku.cells.len() {
-                print!(",");
+        // The rules after the difficulty are made from the registry, like "XRule" or "LayoutRule ; 9 ; samurai"
+        let mut rules = vec![];
+        for source in args().skip(3) {
+            match generation_rule(&source) {
+                Ok(rule) => rules.push(rule),
+                Err(err) => {
+                    println!("Failed to make the rule {source}: {err}");
+                    println!("The rules are: {}", rule_names().join(", "));
+                    return;
+                }
             }
         }
-        println!("");
+        if rules.is_empty() {
+            rules.push(SquareRule::new());
+        }
+
+        let (sudoku, _) = match Sudoku::generate_with_size(9, rules, None, difficulty) {
+            Ok(generated) => generated,
+            Err(err) => {
+                println!("Failed to generate: {err}");
+                return;
+            }
+        };
+
+        println!("{}", sudoku.to_psf());
         return;
     }
 
@@ -67,6 +85,35 @@ fn main() {
         }
     };
 
+    // The solution is printed in the alphabet of the file, unless another is chosen with --alphabet
+    if let Some(alphabet_name) = args().skip_while(|arg| arg != "--alphabet").nth(1) {
+        match alphabet_name.parse::<Alphabet>() {
+            Ok(alphabet) => sudoku.metadata.alphabet = Some(alphabet),
+            Err(err) => {
+                println!("{err}");
+                return;
+            }
+        }
+    }
+
+    // Draws the puzzle to a file instead of solving it, with the candidates or the solution when they are asked for
+    if let Some(svg_filename) = args().skip_while(|arg| arg != "--svg").nth(1) {
+        let options = SvgOptions {
+            candidates: args().any(|arg| arg == "--candidates"),
+            solution: args().any(|arg| arg == "--solution"),
+            ..SvgOptions::default()
+        };
+        match sudoku.to_svg(&options) {
+            Ok(svg) => {
+                if fs::write(&svg_filename, svg).is_err() {
+                    println!("Failed to write {svg_filename}");
+                }
+            }
+            Err(err) => println!("Failed to draw the sudoku {err}"),
+        }
+        return;
+    }
+
     const N: u32 = 0;
 
     let post_parse = pre_parse.elapsed();
@@ -0,0 +1,141 @@
+// This file is the metadata of a puzzle, which PSF writes as lines of "# key: value" before the rules.
+// The known keys get their own fields, and the rest are kept as they were read so nothing is lost.
+
+use std::str::FromStr;
+
+use crate::{formats::alphabet::Alphabet, sudoku::Difficulty};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Metadata {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    // The rated difficulty, which is a Difficulty for our own puzzles but may be any rating for puzzles from elsewhere
+    pub rating: Option<String>,
+    pub source: Option<String>,
+    // The rules of the puzzle written out for the player
+    pub description: Option<String>,
+    // The alphabet the digits of the grid are written in, when it is not the decimal numbers
+    pub alphabet: Option<Alphabet>,
+    // Every digit of the solution, in the same order as the cells
+    pub solution: Option<Vec<u16>>,
+    // The other keys, kept in the order they were read
+    pub other: Vec<(String, String)>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.fields()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn set(&mut self, key: &str, value: String) -> Result<(), String> {
+        match key {
+            "id" => self.id = Some(value),
+            "title" => self.title = Some(value),
+            "author" => self.author = Some(value),
+            "rating" => self.rating = Some(value),
+            "source" => self.source = Some(value),
+            "description" => self.description = Some(value),
+            "alphabet" => self.alphabet = Some(value.parse()?),
+            "solution" => {
+                let solution = value
+                    .split(',')
+                    .map(|digit| digit.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("Invalid solution {value}"))?;
+                self.solution = Some(solution);
+            }
+            _ => match self.other.iter_mut().find(|(k, _)| k == key) {
+                Some((_, old)) => *old = value,
+                None => self.other.push((key.to_owned(), value)),
+            },
+        }
+        Ok(())
+    }
+
+    // Reads a line like "# title: Knights", which must start with #
+    pub fn read_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line
+            .trim()
+            .strip_prefix('#')
+            .and_then(|field| field.split_once(':'))
+            .ok_or_else(|| format!("Invalid metadata {line}"))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("Invalid metadata {line}"));
+        }
+        self.set(key, value.trim().to_owned())
+    }
+
+    // The keys and values that are set, the known keys first
+    pub fn fields(&self) -> Vec<(&str, String)> {
+        let solution = self.solution.as_ref().map(|solution| {
+            solution
+                .iter()
+                .map(|digit| digit.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        });
+        [
+            ("id", self.id.clone()),
+            ("title", self.title.clone()),
+            ("author", self.author.clone()),
+            ("rating", self.rating.clone()),
+            ("source", self.source.clone()),
+            ("description", self.description.clone()),
+            ("alphabet", self.alphabet.as_ref().map(ToString::to_string)),
+            ("solution", solution),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .chain(self.other.iter().map(|(k, v)| (k.as_str(), v.clone())))
+        .collect()
+    }
+
+    // The lines PSF writes before the rules, each ending with a newline
+    pub fn to_psf(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|(key, value)| format!("# {key}: {value}\n"))
+            .collect()
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        Difficulty::from_str(self.rating.as_deref()?).ok()
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn metadata_test() {
+    let mut metadata = Metadata::default();
+    assert!(metadata.is_empty());
+
+    metadata.read_line("# title: Knights: the return").unwrap();
+    metadata.read_line("#rating:Hard").unwrap();
+    metadata.read_line("# solution: 1,2, 2,1").unwrap();
+    metadata.read_line("# setter: Katinka").unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("Knights: the return"));
+    assert!(matches!(metadata.difficulty(), Some(Difficulty::Hard)));
+    assert_eq!(metadata.solution, Some(vec![1, 2, 2, 1]));
+    assert_eq!(metadata.get("setter").as_deref(), Some("Katinka"));
+    assert_eq!(
+        metadata.to_psf(),
+        "# title: Knights: the return\n# rating: Hard\n# solution: 1,2,2,1\n# setter: Katinka\n"
+    );
+
+    assert!(metadata.read_line("# no colon").is_err());
+    assert!(metadata.read_line("title: no hash").is_err());
+    assert!(metadata.read_line("# solution: 1,a").is_err());
+    assert!(metadata.read_line("# alphabet: roman").is_err());
+}
@@ -0,0 +1,342 @@
+// This file is all the logic and tests pertaining to the Between line rule
+
+use super::{clue::Clue, king_neighbours, psf_list, DynRule, Rule};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct BetweenLineRule {
+    // Vector of lines contaning indexes, where the first and last index are the circles.
+    // Every digit between the circles must be strictly between the digits in the circles.
+    pub between_clue: Vec<Vec<usize>>,
+}
+
+impl BetweenLineRule {
+    // Every line needs two circles and at least one cell between them
+    pub const MIN_LINE_LEN: usize = 3;
+
+    pub fn new(between_clue: Vec<Vec<usize>>) -> DynRule {
+        DynRule(Box::new(BetweenLineRule { between_clue }))
+    }
+}
+
+// Returns true if the cell has a candidate strictly between low and high
+fn has_between(cell: &Cell, low: u16, high: u16) -> bool {
+    cell.available
+        .iter()
+        .any(|value| low < *value && *value < high)
+}
+
+impl Rule for BetweenLineRule {
+    // Digits on a line may repeat, so placing a digit does not remove it from the rest of the line
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // A circle keeps a candidate, if the other circle has a candidate so every cell on the line
+    // still has a candidate strictly between the two.
+    // A cell on the line keeps a candidate, if one circle can be below it while the other is above it.
+    // The ranges tighten as the circles resolve, and placed digits are checked as well,
+    // so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+
+        for line in &self.between_clue {
+            let [first, inner @ .., last] = line.as_slice() else {
+                continue;
+            };
+            let (first, last) = (*first, *last);
+
+            for (circle, other) in [(first, last), (last, first)] {
+                for value in &sudoku.cells[circle].available {
+                    let supported = sudoku.cells[other].available.iter().any(|other_value| {
+                        let low = *value.min(other_value);
+                        let high = *value.max(other_value);
+                        low < high
+                            && inner
+                                .iter()
+                                .all(|index| has_between(&sudoku.cells[*index], low, high))
+                    });
+                    if !supported {
+                        big_buffer.push((*value, circle));
+                    }
+                }
+            }
+
+            let first_cell = &sudoku.cells[first];
+            let last_cell = &sudoku.cells[last];
+            let (Some(first_min), Some(first_max), Some(last_min), Some(last_max)) = (
+                first_cell.available.iter().min(),
+                first_cell.available.iter().max(),
+                last_cell.available.iter().min(),
+                last_cell.available.iter().max(),
+            ) else {
+                continue;
+            };
+
+            for index in inner {
+                for value in &sudoku.cells[*index].available {
+                    let first_below = first_min < value && value < last_max;
+                    let first_above = last_min < value && value < first_max;
+                    if !first_below && !first_above {
+                        big_buffer.push((*value, *index));
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.between_clue.iter().all(|line| {
+            let [first, inner @ .., last] = line.as_slice() else {
+                return false;
+            };
+            let first = sudoku.cells[*first].available[0];
+            let last = sudoku.cells[*last].available[0];
+            let low = first.min(last);
+            let high = first.max(last);
+
+            inner.iter().all(|index| {
+                let value = sudoku.cells[*index].available[0];
+                low < value && value < high
+            })
+        })
+    }
+
+    // Creates lines by walking from a random circle to neighbouring cells.
+    // A small digit in the start circle makes it the low end of the line, and a large digit the high end.
+    // The walk only visits digits on the far side of the start circle, and ends in a circle beyond all of them.
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        let value = |index: usize| cells[index].available[0];
+        let height = cells.len() / width;
+        // The cells outside the grids of a layout are 0, and are never part of a line
+        let mut seen: Vec<usize> = self.between_clue.iter().flatten().copied().collect();
+        seen.extend((0..cells.len()).filter(|index| value(*index) == 0));
+
+        'lines: for _ in 0..size {
+            let start = random::<usize>() % cells.len();
+            if seen.contains(&start) {
+                continue;
+            }
+
+            let start_is_low = value(start) as usize * 2 <= size;
+            let beyond = |value: u16, bound: u16| {
+                if start_is_low {
+                    value > bound
+                } else {
+                    value < bound
+                }
+            };
+
+            let mut line = vec![start];
+            let mut extreme = value(start);
+            let inner_len = 1 + random::<usize>() % (size / 3).max(1);
+
+            for _ in 0..inner_len {
+                let options: Vec<usize> = king_neighbours(width, height, line[line.len() - 1])
+                    .filter(|index| {
+                        !seen.contains(index)
+                            && !line.contains(index)
+                            && beyond(value(*index), value(start))
+                    })
+                    .collect();
+                if options.is_empty() {
+                    continue 'lines;
+                }
+
+                let next = options[random::<usize>() % options.len()];
+                if beyond(value(next), extreme) {
+                    extreme = value(next);
+                }
+                line.push(next);
+            }
+
+            let ends: Vec<usize> = king_neighbours(width, height, line[line.len() - 1])
+                .filter(|index| {
+                    !seen.contains(index) && !line.contains(index) && beyond(value(*index), extreme)
+                })
+                .collect();
+            if ends.is_empty() {
+                continue;
+            }
+            line.push(ends[random::<usize>() % ends.len()]);
+
+            seen.extend(&line);
+            self.between_clue.push(line);
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.between_clue
+            .iter()
+            .map(|line| Clue::Line {
+                cells: line.clone(),
+                label: None,
+            })
+            .collect()
+    }
+
+    // A line also needs a cell between its circles, which the clues of other lines do not
+    fn validate(&self, size: usize, width: usize, height: usize) -> Result<(), String> {
+        let error = |e: String| format!("{}: {e}", self.get_name());
+        if let Some(line) = self
+            .between_clue
+            .iter()
+            .find(|line| line.len() < Self::MIN_LINE_LEN)
+        {
+            return Err(error(format!(
+                "The line {line:?} has less than {} cells",
+                Self::MIN_LINE_LEN
+            )));
+        }
+        self.clues()
+            .iter()
+            .try_for_each(|clue| clue.validate(size, width, height))
+            .map_err(error)
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "BetweenLineRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.between_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "BetweenLineRule".to_owned();
+        for line in &self.between_clue {
+            psf += &format!(" ;{}", psf_list(line));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::BetweenLineRule {
+            between_clue: self.between_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn between_line_multi_remove_test() {
+    let mut sudoku = Sudoku::new(9, vec![]);
+    let between_line_rule = BetweenLineRule {
+        between_clue: vec![vec![0, 1, 2]],
+    };
+    let mut big_buffer = vec![];
+
+    // the digit in the middle can not be the smallest or largest digit
+    let res = between_line_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(1, 1), (9, 1)].as_slice());
+
+    // with an 8 in one circle, the other circle needs room for a digit between them.
+    // The 8 is already removed from the row
+    sudoku.set_cell(8, 0).unwrap();
+    let res = between_line_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(7, 2), (9, 2), (1, 1), (9, 1)].as_slice());
+}
+
+#[test]
+fn between_line_finished_legal_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+
+    let between_line_rule = BetweenLineRule {
+        between_clue: vec![vec![0, 1, 2, 3], vec![9, 8, 4]],
+    };
+    assert!(between_line_rule.finished_legal(&sudoku));
+
+    let between_line_rule = BetweenLineRule {
+        between_clue: vec![vec![0, 1, 2]],
+    };
+    assert!(between_line_rule.finished_legal(&sudoku));
+
+    let between_line_rule = BetweenLineRule {
+        between_clue: vec![vec![0, 5, 1]],
+    };
+    assert!(!between_line_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn between_line_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut between_line_rule = BetweenLineRule {
+        between_clue: vec![],
+    };
+
+    for _ in 0..10 {
+        between_line_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+    }
+
+    assert!(between_line_rule.finished_legal(&sudoku));
+    for line in &between_line_rule.between_clue {
+        assert!(line.len() >= 3);
+        assert!(line
+            .windows(2)
+            .all(|pair| king_neighbours(sudoku.size, sudoku.size, pair[0])
+                .any(|index| index == pair[1])));
+    }
+}
+
+#[test]
+fn between_line_short_line_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    assert!("BetweenLineRule ; 0,1".parse::<DynRule>().is_err());
+    assert!("BetweenLineRule ; 0".parse::<DynRule>().is_err());
+    assert!("BetweenLineRule ; 0,1,2 ; 5".parse::<DynRule>().is_err());
+
+    // a line without both circles is never finished, and is skipped when removing
+    let between_line_rule = BetweenLineRule {
+        between_clue: vec![vec![0], vec![]],
+    };
+    assert!(!between_line_rule.finished_legal(&sudoku));
+    assert!(between_line_rule.validate(4, 4, 4).is_err());
+    assert!(BetweenLineRule {
+        between_clue: vec![vec![0, 1]],
+    }
+    .validate(4, 4, 4)
+    .is_err());
+    assert!(between_line_rule.validate(4, 4, 4).is_err());
+    assert!(BetweenLineRule {
+        between_clue: vec![vec![0, 1]],
+    }
+    .validate(4, 4, 4)
+    .is_err());
+    let mut big_buffer = vec![];
+    assert!(between_line_rule
+        .multi_remove(&sudoku, &mut big_buffer)
+        .is_empty());
+}
+
+#[test]
+fn between_line_parse_test() {
+    let rule: DynRule = "BetweenLineRule ; 0,1,2 ; 10,11,20,21".parse().unwrap();
+    assert_eq!(rule.get_name(), "BetweenLineRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
@@ -0,0 +1,200 @@
+// This file describes the clues of the rules, so they can be drawn and checked without knowing the type of the rule
+
+// The side of the grid an outside clue is written on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// Clues are only drawn, so they are serialized to be sent but never read back
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Clue {
+    // A mark on the border between two cells, like an X or a dot.
+    // The value is written on the mark, for the rules where the marks have different values.
+    Domino {
+        cells: (usize, usize),
+        value: Option<u16>,
+    },
+    // A line through the cells in the order they are listed, like a thermometer from the bulb.
+    // The label tells lines of the same rule apart, like the slow thermometers.
+    Line {
+        cells: Vec<usize>,
+        label: Option<&'static str>,
+    },
+    // A group of cells drawn with a dashed border, and the label written in the corner
+    Cage {
+        cells: Vec<usize>,
+        label: String,
+    },
+    // A mark inside a single cell, like the square of an even cell
+    Cell {
+        index: usize,
+        label: &'static str,
+    },
+    // A circle on the bottom right corner of the index, with the digits written in it
+    Corner {
+        index: usize,
+        digits: Vec<u16>,
+    },
+    // A number outside the grid next to the row or column at the position
+    Outside {
+        side: Side,
+        position: usize,
+        value: u16,
+    },
+    // A group of cells where every digit is different, drawn as a shaded area
+    Region {
+        cells: Vec<usize>,
+    },
+}
+
+impl Clue {
+    // Checks that the clue fits a grid of width by height cells, with the digits from 1 to size.
+    // The cells of a domino must share a side, and the cells next to each other on a line must touch.
+    pub fn validate(&self, size: usize, width: usize, height: usize) -> Result<(), String> {
+        let cell_count = width * height;
+        let in_range = |value: u16, max: usize| {
+            if (1..=max).contains(&(value as usize)) {
+                Ok(())
+            } else {
+                Err(format!("The value {value} is not between 1 and {max}"))
+            }
+        };
+        let in_bounds = |index: &usize| {
+            if *index < cell_count {
+                Ok(())
+            } else {
+                Err(format!("The cell {index} is outside the grid"))
+            }
+        };
+        let position = |index: usize| ((index % width) as isize, (index / width) as isize);
+
+        match self {
+            Clue::Domino {
+                cells: (left, right),
+                value,
+            } => {
+                in_bounds(left)?;
+                in_bounds(right)?;
+                // The two digits of a domino are different, so they add up to at most 2 * size - 1
+                if let Some(value) = value {
+                    in_range(*value, 2 * size - 1)?;
+                }
+                let ((lx, ly), (rx, ry)) = (position(*left), position(*right));
+                if lx.abs_diff(rx) + ly.abs_diff(ry) != 1 {
+                    return Err(format!(
+                        "The cells {left} and {right} are not next to each other"
+                    ));
+                }
+            }
+            Clue::Line { cells, .. } => {
+                if cells.len() < 2 {
+                    return Err(format!("The line {cells:?} has less than 2 cells"));
+                }
+                cells.iter().try_for_each(in_bounds)?;
+                for pair in cells.windows(2) {
+                    let ((ax, ay), (bx, by)) = (position(pair[0]), position(pair[1]));
+                    if ax.abs_diff(bx).max(ay.abs_diff(by)) != 1 {
+                        return Err(format!(
+                            "The line is broken between the cells {} and {}",
+                            pair[0], pair[1]
+                        ));
+                    }
+                }
+            }
+            Clue::Cage { cells, .. } | Clue::Region { cells } => {
+                cells.iter().try_for_each(in_bounds)?;
+            }
+            Clue::Cell { index, .. } => in_bounds(index)?,
+            // The corner is shared by the four cells right and below the index
+            Clue::Corner { index, digits } => {
+                in_bounds(index)?;
+                if index % width == width - 1 || index / width == height - 1 {
+                    return Err(format!("The cell {index} has no corner inside the grid"));
+                }
+                if digits.len() > 4 {
+                    return Err(format!("The corner {index} has more digits than cells"));
+                }
+                digits.iter().try_for_each(|digit| in_range(*digit, size))?;
+            }
+            Clue::Outside {
+                side,
+                position,
+                value,
+            } => {
+                in_range(*value, size)?;
+                let length = match side {
+                    Side::Top | Side::Bottom => width,
+                    Side::Left | Side::Right => height,
+                };
+                if *position >= length {
+                    return Err(format!(
+                        "The position {position} is outside the {side:?} side"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn clue_validate_test() {
+    let domino = |left, right| Clue::Domino {
+        cells: (left, right),
+        value: None,
+    };
+    assert!(domino(0, 1).validate(4, 4, 4).is_ok());
+    assert!(domino(0, 4).validate(4, 4, 4).is_ok());
+    // the end of one row is not next to the start of the next
+    assert!(domino(3, 4).validate(4, 4, 4).is_err());
+    assert!(domino(15, 16).validate(4, 4, 4).is_err());
+
+    let line = |cells: Vec<usize>| Clue::Line { cells, label: None };
+    assert!(line(vec![0, 5, 6, 2]).validate(4, 4, 4).is_ok());
+    assert!(line(vec![0, 2]).validate(4, 4, 4).is_err());
+    assert!(line(vec![3, 4]).validate(4, 4, 4).is_err());
+    assert!(line(vec![0]).validate(4, 4, 4).is_err());
+
+    // the values have to be digits of the grid, or a sum of two digits on a domino
+    let dot = |value| Clue::Domino {
+        cells: (0, 1),
+        value: Some(value),
+    };
+    assert!(dot(7).validate(4, 4, 4).is_ok());
+    assert!(dot(8).validate(4, 4, 4).is_err());
+    assert!(dot(0).validate(4, 4, 4).is_err());
+    let corner = |digits| Clue::Corner { index: 0, digits };
+    assert!(corner(vec![1, 4]).validate(4, 4, 4).is_ok());
+    assert!(corner(vec![5]).validate(4, 4, 4).is_err());
+    assert!(corner(vec![1, 1, 2, 2, 3]).validate(4, 4, 4).is_err());
+    let outside = |value| Clue::Outside {
+        side: Side::Top,
+        position: 0,
+        value,
+    };
+    assert!(outside(4).validate(4, 4, 4).is_ok());
+    assert!(outside(0).validate(4, 4, 4).is_err());
+    assert!(outside(5).validate(4, 4, 4).is_err());
+
+    assert!(Clue::Corner {
+        index: 14,
+        digits: vec![]
+    }
+    .validate(4, 4, 4)
+    .is_err());
+    assert!(Clue::Outside {
+        side: Side::Left,
+        position: 4,
+        value: 1
+    }
+    .validate(4, 4, 4)
+    .is_err());
+}
@@ -128,6 +128,12 @@ impl Rule for ColumnRule {
     fn get_name(&self) -> &'static str {
         "ColumnRule"
     }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ColumnRule
+    }
 }
 
 //########################### TEST ###############################
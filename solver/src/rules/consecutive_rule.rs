@@ -1,7 +1,7 @@
 // This file is all the logic and tests pertaining to the X rule
 // Author Peter s224759
 
-use super::{DynRule, Rule};
+use super::{active_pairs, clue::Clue, DynRule, Rule};
 use rand::random;
 use std::fmt::Debug;
 
@@ -105,28 +105,11 @@ impl Rule for ConsecutiveRule {
     }
 
     // creates cules for the consecutive rule.
-    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize) {
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
         // search the entire sudoku for all possible consecutive dominos
-        for index in 0..cells.len() {
-            if let Some(current) = cells[index].available.get(0) {
-                if index + 1 >= cells.len() {
-                    continue;
-                }
-                if let Some(left) = cells[index + 1].available.get(0) {
-                    if current + 1 == *left || *current == left + 1 && (index + 1) % size != 0 {
-                        self.consecutive_clue.push((index, index + 1));
-                    }
-                }
-                if index + size >= cells.len() {
-                    continue;
-                }
-                if let Some(below) = cells[index + size].available.get(0) {
-                    if current + 1 == *below || *current == below + 1 && index + size < cells.len()
-                    {
-                        // consecutive rule should have (index , below)
-                        self.consecutive_clue.push((index, index + size));
-                    }
-                }
+        for (left, right) in active_pairs(cells, width) {
+            if cells[left].available[0].abs_diff(cells[right].available[0]) == 1 {
+                self.consecutive_clue.push((left, right));
             }
         }
         // remove some of the generated consecutive pairs
@@ -139,6 +122,10 @@ impl Rule for ConsecutiveRule {
         }
     }
 
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
     fn boxed_clone(&self) -> DynRule {
         DynRule(Box::new(self.clone()))
     }
@@ -147,19 +134,33 @@ impl Rule for ConsecutiveRule {
         "ConsecutiveRule"
     }
 
-    fn to_consecutive_rule(&mut self) -> Option<&mut ConsecutiveRule> {
-        Some(self)
+    fn clues(&self) -> Vec<Clue> {
+        self.consecutive_clue
+            .iter()
+            .map(|(left, right)| Clue::Domino {
+                cells: (*left, *right),
+                value: None,
+            })
+            .collect()
     }
 
     fn no_of_clues(&self) -> usize {
         return self.consecutive_clue.len();
     }
-    fn print_self(&self) -> bool {
-        print!("ConsecutiveRule");
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ConsecutiveRule".to_owned();
         for (x, y) in &self.consecutive_clue {
-            print!(" ; {x},{y}")
+            psf += &format!(" ; {x},{y}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ConsecutiveRule {
+            consecutive_clue: self.consecutive_clue.clone(),
         }
-        true
     }
 }
 
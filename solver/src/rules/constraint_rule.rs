@@ -0,0 +1,406 @@
+// This file is all the logic and tests pertaining to the Constraint rule
+
+use super::{clue::Clue, orthogonal_neighbours, psf_list, DynRule, Rule};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+// The search for placements of a group stops after this many steps,
+// and then nothing is removed from the group
+const SEARCH_BUDGET: usize = 10000;
+
+// The conditions a group of cells can be given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Predicate {
+    // The digits add up to the value
+    Sum(u16),
+    // The digits multiply to the value
+    Product(u64),
+    // Every digit is different
+    Distinct,
+    // The digits are strictly increasing in the order of the group
+    Increasing,
+    // Neighbouring digits in the order of the group differ by exactly the value
+    Difference(u16),
+}
+
+impl Predicate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Predicate::Sum(_) => "sum",
+            Predicate::Product(_) => "product",
+            Predicate::Distinct => "distinct",
+            Predicate::Increasing => "increasing",
+            Predicate::Difference(_) => "difference",
+        }
+    }
+
+    // Returns the value of the predicate, for the predicates that have one
+    pub fn value(&self) -> Option<u64> {
+        match self {
+            Predicate::Sum(sum) => Some(*sum as u64),
+            Predicate::Product(product) => Some(*product),
+            Predicate::Difference(difference) => Some(*difference as u64),
+            Predicate::Distinct | Predicate::Increasing => None,
+        }
+    }
+
+    // Returns false if the digits placed at the start of the group can never satisfy the predicate.
+    // When the group is complete the predicate must be satisfied exactly.
+    fn allows(&self, digits: &[u16], complete: bool) -> bool {
+        match self {
+            Predicate::Sum(sum) => {
+                let total: u16 = digits.iter().sum();
+                total == *sum || (!complete && total < *sum)
+            }
+            Predicate::Product(product) => {
+                let total = digits
+                    .iter()
+                    .try_fold(1u64, |total, digit| total.checked_mul(*digit as u64));
+                match total {
+                    Some(total) if complete => total == *product,
+                    Some(total) => total != 0 && product % total == 0,
+                    None => false,
+                }
+            }
+            Predicate::Distinct => digits
+                .iter()
+                .enumerate()
+                .all(|(i, digit)| !digits[i + 1..].contains(digit)),
+            Predicate::Increasing => digits.windows(2).all(|pair| pair[0] < pair[1]),
+            Predicate::Difference(difference) => digits
+                .windows(2)
+                .all(|pair| pair[0].abs_diff(pair[1]) == *difference),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintRule {
+    // touples of (predicate, group of indexes). The digits of the group, in the listed order, must satisfy the predicate.
+    pub constraint_clue: Vec<(Predicate, Vec<usize>)>,
+}
+
+impl ConstraintRule {
+    pub fn new(constraint_clue: Vec<(Predicate, Vec<usize>)>) -> DynRule {
+        DynRule(Box::new(ConstraintRule { constraint_clue }))
+    }
+}
+
+// Tries every placement of digits in the group that agrees with the candidates,
+// and marks the digits of each placement that satisfies the predicate as possible.
+// Returns false if the budget ran out before every placement was tried.
+fn search(
+    sudoku: &Sudoku,
+    predicate: &Predicate,
+    group: &[usize],
+    placed: &mut Vec<u16>,
+    possible: &mut [u32],
+    budget: &mut usize,
+) -> bool {
+    if *budget == 0 {
+        return false;
+    }
+    *budget -= 1;
+
+    let complete = placed.len() == group.len();
+    if !predicate.allows(placed, complete) {
+        return true;
+    }
+    if complete {
+        for (digit, mask) in placed.iter().zip(possible.iter_mut()) {
+            *mask |= 1 << digit;
+        }
+        return true;
+    }
+
+    for digit in &sudoku.cells[group[placed.len()]].available {
+        placed.push(*digit);
+        let finished = search(sudoku, predicate, group, placed, possible, budget);
+        placed.pop();
+        if !finished {
+            return false;
+        }
+    }
+    true
+}
+
+impl Rule for ConstraintRule {
+    // Returns all indexes in the groups where every digit is different, that the index is part of
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        for (predicate, group) in &self.constraint_clue {
+            if matches!(predicate, Predicate::Distinct | Predicate::Increasing)
+                && group.contains(&index)
+            {
+                buffer.extend(group);
+            }
+        }
+        buffer
+    }
+
+    // Forward checking of every group. A candidate is removed,
+    // if no placement of the rest of the group together with the candidate satisfies the predicate.
+    // Placed digits are checked as well, so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        let mut placed = vec![];
+        let mut possible = vec![];
+
+        for (predicate, group) in &self.constraint_clue {
+            placed.clear();
+            possible.clear();
+            possible.resize(group.len(), 0);
+            let mut budget = SEARCH_BUDGET;
+            if !search(
+                sudoku,
+                predicate,
+                group,
+                &mut placed,
+                &mut possible,
+                &mut budget,
+            ) {
+                continue;
+            }
+
+            for (position, index) in group.iter().enumerate() {
+                for value in &sudoku.cells[*index].available {
+                    // A cell can be in several groups, but each candidate is only removed once
+                    if possible[position] & (1 << value) == 0
+                        && !big_buffer.contains(&(*value, *index))
+                    {
+                        big_buffer.push((*value, *index));
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.constraint_clue.iter().all(|(predicate, group)| {
+            let digits: Vec<u16> = group
+                .iter()
+                .map(|index| sudoku.cells[*index].available[0])
+                .collect();
+            predicate.allows(&digits, true)
+        })
+    }
+
+    // Grows small groups of orthogonally connected cells in the solved sudoku,
+    // and gives each of them the sum or the product of its digits
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        let height = cells.len() / width;
+        // The cells outside the grids of a layout are 0, and are never part of a group
+        let mut seen: Vec<usize> = self
+            .constraint_clue
+            .iter()
+            .flat_map(|(_, group)| group)
+            .copied()
+            .chain((0..cells.len()).filter(|index| cells[*index].available[0] == 0))
+            .collect();
+
+        for _ in 0..size / 2 {
+            let start = random::<usize>() % cells.len();
+            if seen.contains(&start) {
+                continue;
+            }
+
+            let mut group = vec![start];
+            let group_len = 2 + random::<usize>() % 2;
+            while group.len() < group_len {
+                let options: Vec<usize> =
+                    orthogonal_neighbours(width, height, group[group.len() - 1])
+                        .filter(|index| !seen.contains(index) && !group.contains(index))
+                        .collect();
+                if options.is_empty() {
+                    break;
+                }
+                group.push(options[random::<usize>() % options.len()]);
+            }
+            if group.len() < 2 {
+                continue;
+            }
+
+            let digits = group.iter().map(|index| cells[*index].available[0]);
+            let predicate = if random::<bool>() {
+                Predicate::Sum(digits.sum())
+            } else {
+                Predicate::Product(digits.map(|digit| digit as u64).product())
+            };
+
+            seen.extend(&group);
+            self.constraint_clue.push((predicate, group));
+        }
+    }
+
+    // The label is the predicate as it is written in the PSF
+    fn clues(&self) -> Vec<Clue> {
+        self.constraint_clue
+            .iter()
+            .map(|(predicate, group)| Clue::Cage {
+                cells: group.clone(),
+                label: match predicate.value() {
+                    Some(value) => format!("{} {value}", predicate.name()),
+                    None => predicate.name().to_owned(),
+                },
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ConstraintRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.constraint_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ConstraintRule".to_owned();
+        for (predicate, group) in &self.constraint_clue {
+            psf += &format!(" ; {}", predicate.name());
+            if let Some(value) = predicate.value() {
+                psf += &format!(" {value}");
+            }
+            psf += &format!(" : {}", psf_list(group));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ConstraintRule {
+            constraint_clue: self.constraint_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn constraint_allows_test() {
+    assert!(Predicate::Sum(10).allows(&[4, 5], false));
+    assert!(!Predicate::Sum(10).allows(&[4, 5], true));
+    assert!(Predicate::Product(12).allows(&[3], false));
+    assert!(!Predicate::Product(12).allows(&[5], false));
+    assert!(Predicate::Product(12).allows(&[3, 4], true));
+    assert!(!Predicate::Distinct.allows(&[1, 2, 1], false));
+    assert!(!Predicate::Increasing.allows(&[1, 3, 2], false));
+    assert!(Predicate::Difference(2).allows(&[1, 3, 1], true));
+    assert!(!Predicate::Difference(2).allows(&[1, 3, 4], true));
+}
+
+#[test]
+fn constraint_multi_remove_test() {
+    let constraint_rule = ConstraintRule {
+        constraint_clue: vec![
+            (Predicate::Sum(3), vec![0, 1]),
+            (Predicate::Product(12), vec![5, 6]),
+        ],
+    };
+    let mut sudoku = Sudoku::new(4, vec![]);
+    let mut big_buffer = vec![];
+
+    // a sum of 3 in two cells is 1 and 2, and a product of 12 is 3 and 4
+    let res = constraint_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        res,
+        vec![
+            (3, 0),
+            (4, 0),
+            (3, 1),
+            (4, 1),
+            (1, 5),
+            (2, 5),
+            (1, 6),
+            (2, 6)
+        ]
+        .as_slice()
+    );
+
+    // with the 4 placed, the 4 is gone from the row and the other cell must be 3
+    sudoku.set_cell(4, 6).unwrap();
+    let res = constraint_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        res,
+        vec![(3, 0), (4, 0), (3, 1), (4, 1), (1, 5), (2, 5)].as_slice()
+    );
+}
+
+#[test]
+fn constraint_increasing_test() {
+    let constraint_rule = ConstraintRule {
+        constraint_clue: vec![(Predicate::Increasing, vec![0, 5, 10, 15])],
+    };
+    let sudoku = Sudoku::new(4, vec![]);
+    let mut big_buffer = vec![];
+
+    let res = constraint_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res.len(), 12);
+
+    let mut buffer = vec![];
+    assert_eq!(
+        constraint_rule.updates(sudoku.size, 5, &mut buffer),
+        vec![0, 5, 10, 15]
+    );
+    assert_eq!(
+        constraint_rule.updates(sudoku.size, 1, &mut buffer),
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn constraint_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut constraint_rule = ConstraintRule {
+        constraint_clue: vec![],
+    };
+
+    for _ in 0..5 {
+        constraint_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+    }
+
+    assert!(constraint_rule.no_of_clues() > 0);
+    assert!(constraint_rule
+        .constraint_clue
+        .iter()
+        .all(|(_, group)| group.len() >= 2));
+    assert!(constraint_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn constraint_parse_test() {
+    let rule: DynRule =
+        "ConstraintRule ; sum 10 : 0,1,2 ; product 12 : 3,4 ; distinct : 5,6,7 ; increasing : 8,9 ; difference 3 : 10,11"
+            .parse()
+            .unwrap();
+    assert_eq!(rule.get_name(), "ConstraintRule");
+    assert_eq!(rule.no_of_clues(), 5);
+
+    assert!("ConstraintRule ; sum : 0,1".parse::<DynRule>().is_err());
+    assert!("ConstraintRule ; median 4 : 0,1"
+        .parse::<DynRule>()
+        .is_err());
+}
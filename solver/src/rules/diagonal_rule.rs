@@ -228,9 +228,14 @@ impl Rule for DiagonalRule {
     fn priority(&self) -> super::ExecutionPriority {
         super::ExecutionPriority::High
     }
-    fn print_self(&self) -> bool {
-        print!("DiagonalRule");
-        true
+    fn to_psf(&self) -> Option<String> {
+        Some("DiagonalRule".to_owned())
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::DiagonalRule
     }
 }
 
@@ -330,10 +335,12 @@ fn locked_diagonal_candidate() {
 fn diagonal_test() {
     let diagonalrule = DiagonalRule::new();
     let mut buffer = vec![];
+    // serde_json can also compare a number with its Value, so an empty vec![] needs a type
+    let empty: Vec<usize> = vec![];
 
     // testing 9x9
     let mut indexes = diagonalrule.updates(9, 11, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(9, 80, &mut buffer);
     assert_eq!(indexes, vec![0, 10, 20, 30, 40, 50, 60, 70, 80]);
     indexes = diagonalrule.updates(9, 0, &mut buffer);
@@ -346,39 +353,39 @@ fn diagonal_test() {
         vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 8, 16, 24, 32, 40, 48, 56, 64, 72]
     );
     indexes = diagonalrule.updates(9, 41, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
 
     // testing 4x4
     indexes = diagonalrule.updates(4, 0, &mut buffer);
     assert_eq!(indexes, vec![0, 5, 10, 15]);
     indexes = diagonalrule.updates(4, 1, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 2, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 3, &mut buffer);
     assert_eq!(indexes, vec![3, 6, 9, 12]);
     indexes = diagonalrule.updates(4, 4, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 5, &mut buffer);
     assert_eq!(indexes, vec![0, 5, 10, 15]);
     indexes = diagonalrule.updates(4, 6, &mut buffer);
     assert_eq!(indexes, vec![3, 6, 9, 12]);
     indexes = diagonalrule.updates(4, 7, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 8, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 9, &mut buffer);
     assert_eq!(indexes, vec![3, 6, 9, 12]);
     indexes = diagonalrule.updates(4, 10, &mut buffer);
     assert_eq!(indexes, vec![0, 5, 10, 15]);
     indexes = diagonalrule.updates(4, 11, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 12, &mut buffer);
     assert_eq!(indexes, vec![3, 6, 9, 12]);
     indexes = diagonalrule.updates(4, 13, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 14, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
     indexes = diagonalrule.updates(4, 15, &mut buffer);
     assert_eq!(indexes, vec![0, 5, 10, 15]);
 
@@ -404,7 +411,7 @@ fn diagonal_test() {
         vec![15, 30, 45, 60, 75, 90, 105, 120, 135, 150, 165, 180, 195, 210, 225, 240]
     );
     indexes = diagonalrule.updates(16, 111, &mut buffer);
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, empty);
 }
 
 #[test]
@@ -0,0 +1,337 @@
+// This file is all the logic and tests pertaining to the Difference dot rule
+
+use super::{
+    active_pairs, clue::Clue, dot_clue_limit, psf_list, push_unsupported, share_house, DynRule,
+    Rule,
+};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+// A generalised ConsecutiveRule, where every dot carries the difference between its two cells
+#[derive(Debug, Clone)]
+pub struct DifferenceDotRule {
+    // touples of (left index, right index, difference), where the two cells must differ by the difference
+    pub difference_clue: Vec<(usize, usize, u16)>,
+    // the differences the dots can have.
+    // These are the differences create_clue marks, and the differences the negative constraint forbids.
+    pub differences: Vec<u16>,
+    // if all dots are given, adjacent cells without a dot may not differ by any of the differences
+    pub negative: bool,
+}
+
+impl DifferenceDotRule {
+    pub fn new(
+        difference_clue: Vec<(usize, usize, u16)>,
+        differences: Vec<u16>,
+        negative: bool,
+    ) -> DynRule {
+        DynRule(Box::new(DifferenceDotRule {
+            difference_clue,
+            differences,
+            negative,
+        }))
+    }
+
+    // The white kropki dots, where the only difference is 1
+    pub fn consecutive(difference_clue: Vec<(usize, usize, u16)>, negative: bool) -> DynRule {
+        Self::new(difference_clue, vec![1], negative)
+    }
+
+    fn has_dot(&self, left: usize, right: usize) -> bool {
+        self.difference_clue
+            .iter()
+            .any(|(l, r, _)| (*l == left && *r == right) || (*l == right && *r == left))
+    }
+
+    // If the cell is placed and the other half is not, returns the only candidate of the other half
+    // that is the difference away from the placed digit
+    fn hidden_single(
+        sudoku: &Sudoku,
+        placed: usize,
+        other: usize,
+        difference: u16,
+    ) -> Option<(u16, usize)> {
+        let placed_cell = &sudoku.cells[placed];
+        let other_cell = &sudoku.cells[other];
+        if !placed_cell.locked_in || other_cell.locked_in {
+            return None;
+        }
+
+        let value = *placed_cell.available.first()?;
+        let mut partners = [value.checked_sub(difference), Some(value + difference)]
+            .into_iter()
+            .flatten()
+            .filter(|partner| other_cell.available.contains(partner));
+
+        match (partners.next(), partners.next()) {
+            (Some(partner), None) => Some((partner, other)),
+            _ => None,
+        }
+    }
+}
+
+impl Rule for DifferenceDotRule {
+    // Updates for domino rules do not affect other cells
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    fn hidden_singles(&self, sudoku: &Sudoku) -> Option<(u16, usize)> {
+        self.difference_clue
+            .iter()
+            .find_map(|(left, right, difference)| {
+                Self::hidden_single(sudoku, *left, *right, *difference)
+                    .or_else(|| Self::hidden_single(sudoku, *right, *left, *difference))
+            })
+    }
+
+    // Removes every candidate on a dot, that is not the difference away from any candidate in the other half.
+    // In negative mode the same is done for the adjacent cells without a dot, where the cells may not differ by any difference.
+    // Placed digits are checked as well, so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        let mut buffer = vec![];
+
+        for (left, right, difference) in &self.difference_clue {
+            let allowed = |a: u16, b: u16| a.abs_diff(b) == *difference;
+            let left_cell = &sudoku.cells[*left];
+            let right_cell = &sudoku.cells[*right];
+
+            let distinct = share_house(sudoku, *left, *right, &mut buffer);
+
+            push_unsupported(left_cell, right_cell, *right, distinct, allowed, big_buffer);
+            push_unsupported(right_cell, left_cell, *left, distinct, allowed, big_buffer);
+        }
+
+        if self.negative {
+            let allowed = |a: u16, b: u16| !self.differences.contains(&a.abs_diff(b));
+
+            for (left, right) in active_pairs(&sudoku.cells, sudoku.width()) {
+                if self.has_dot(left, right) {
+                    continue;
+                }
+                let left_cell = &sudoku.cells[left];
+                let right_cell = &sudoku.cells[right];
+                let distinct = share_house(sudoku, left, right, &mut buffer);
+
+                // A cell can only rule out a candidate next to it, if all its candidates are the candidate
+                // or one of the digits above or below it
+                let max_len = self.differences.len() * 2 + 1;
+                if left_cell.available.len() <= max_len {
+                    push_unsupported(left_cell, right_cell, right, distinct, allowed, big_buffer);
+                }
+                if right_cell.available.len() <= max_len {
+                    push_unsupported(right_cell, left_cell, left, distinct, allowed, big_buffer);
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        let value = |index: usize| sudoku.cells[index].available[0];
+
+        self.difference_clue
+            .iter()
+            .all(|(left, right, difference)| value(*left).abs_diff(value(*right)) == *difference)
+            && (!self.negative
+                || active_pairs(&sudoku.cells, sudoku.width()).all(|(left, right)| {
+                    self.has_dot(left, right)
+                        || !self
+                            .differences
+                            .contains(&value(left).abs_diff(value(right)))
+                }))
+    }
+
+    // Marks all the adjacent cells in the solved sudoku that differ by one of the differences
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        for (left, right) in active_pairs(cells, width) {
+            let difference = cells[left].available[0].abs_diff(cells[right].available[0]);
+            if self.differences.contains(&difference) && !self.has_dot(left, right) {
+                self.difference_clue.push((left, right, difference));
+            }
+        }
+
+        // In negative mode every dot has to be given, otherwise remove some of them
+        let count = self.difference_clue.len();
+        let limit = dot_clue_limit(size);
+        if !self.negative && count > limit {
+            for i in 0..count - limit {
+                self.difference_clue.remove(random::<usize>() % (count - i));
+            }
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.difference_clue
+            .iter()
+            .map(|(left, right, value)| Clue::Domino {
+                cells: (*left, *right),
+                value: Some(*value),
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "DifferenceDotRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.difference_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = format!(
+            "DifferenceDotRule ; differences {}",
+            psf_list(&self.differences)
+        );
+        if self.negative {
+            psf += " ; negative";
+        }
+        for (left, right, difference) in &self.difference_clue {
+            psf += &format!(" ; {left},{right},{difference}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::DifferenceDotRule {
+            difference_clue: self.difference_clue.clone(),
+            differences: self.differences.clone(),
+            negative: self.negative,
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn difference_dot_multi_remove_test() {
+    let difference_dot_rule = DifferenceDotRule {
+        difference_clue: vec![(1, 2, 2), (4, 8, 3)],
+        differences: vec![2, 3],
+        negative: false,
+    };
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            difference_dot_rule.boxed_clone(),
+        ],
+    );
+    let mut big_buffer = vec![];
+
+    // a difference of 3 can only be 1 and 4 on a 4x4
+    let res = difference_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(2, 8), (3, 8), (2, 4), (3, 4)].as_slice());
+
+    sudoku.set_cell(1, 1).unwrap();
+    let res = difference_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        res,
+        vec![(2, 2), (4, 2), (2, 8), (3, 8), (4, 8), (2, 4), (3, 4)].as_slice()
+    );
+}
+
+#[test]
+fn difference_dot_hidden_test() {
+    let difference_dot_rule = DifferenceDotRule {
+        difference_clue: vec![(1, 2, 2)],
+        differences: vec![2],
+        negative: false,
+    };
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            difference_dot_rule.boxed_clone(),
+        ],
+    );
+
+    assert_eq!(difference_dot_rule.hidden_singles(&sudoku), None);
+
+    // 1 can only be next to a 3
+    sudoku.set_cell(1, 1).unwrap();
+    assert_eq!(difference_dot_rule.hidden_singles(&sudoku), Some((3, 2)));
+}
+
+#[test]
+fn difference_dot_negative_test() {
+    let difference_dot_rule = DifferenceDotRule {
+        difference_clue: vec![(0, 1, 1)],
+        differences: vec![1],
+        negative: true,
+    };
+    let mut sudoku = Sudoku::new(4, vec![super::square_rule::SquareRule::new()]);
+    let mut big_buffer = vec![];
+
+    sudoku.set_cell(2, 5).unwrap();
+    let res = difference_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    // The 1 is removed from the dot, and the 1 and 3 are removed around the 2 without a dot
+    assert_eq!(
+        res,
+        vec![
+            (1, 1),
+            (1, 0),
+            (1, 1),
+            (3, 1),
+            (1, 4),
+            (3, 4),
+            (1, 6),
+            (3, 6),
+            (1, 9),
+            (3, 9)
+        ]
+        .as_slice()
+    );
+}
+
+#[test]
+fn difference_dot_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut difference_dot_rule = DifferenceDotRule {
+        difference_clue: vec![],
+        differences: vec![3],
+        negative: true,
+    };
+
+    difference_dot_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    assert_eq!(
+        difference_dot_rule.difference_clue,
+        vec![(5, 6, 3), (5, 9, 3), (6, 10, 3), (9, 10, 3)]
+    );
+    assert!(difference_dot_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn difference_dot_parse_test() {
+    let rule: DynRule = "DifferenceDotRule ; differences 1,3 ; 0,1,1 ; 1,2,3"
+        .parse()
+        .unwrap();
+    assert_eq!(rule.get_name(), "DifferenceDotRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
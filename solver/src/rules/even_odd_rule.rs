@@ -0,0 +1,154 @@
+// This file is all the logic and tests pertaining to the Even/odd rule
+
+use super::{clue::Clue, DynRule, Rule};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct EvenOddRule {
+    // touples of (index, is_even). Even cells are drawn as squares and odd cells as circles
+    pub even_odd_clue: Vec<(usize, bool)>,
+}
+
+impl EvenOddRule {
+    pub fn new(even_odd_clue: Vec<(usize, bool)>) -> DynRule {
+        DynRule(Box::new(EvenOddRule { even_odd_clue }))
+    }
+}
+
+impl Rule for EvenOddRule {
+    // A shaded cell does not affect any other cells
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // Removes all the digits with the wrong parity from the shaded cells.
+    // This also removes a placed digit with the wrong parity, so the solver drops the branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        for (index, is_even) in &self.even_odd_clue {
+            for value in &sudoku.cells[*index].available {
+                if (value & 1 == 0) != *is_even {
+                    big_buffer.push((*value, *index));
+                }
+            }
+        }
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.even_odd_clue
+            .iter()
+            .all(|(index, is_even)| (sudoku.cells[*index].available[0] & 1 == 0) == *is_even)
+    }
+
+    // Shades random cells of the solved sudoku with the parity of their digit
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, _width: usize) {
+        // The cells outside the grids of a layout are 0, which would be marked even
+        let mut indexes: Vec<usize> = (0..cells.len())
+            .filter(|index| cells[*index].available[0] != 0)
+            .filter(|index| !self.even_odd_clue.iter().any(|(i, _)| i == index))
+            .collect();
+
+        for _ in 0..size {
+            if indexes.is_empty() {
+                break;
+            }
+            let index = indexes.remove(random::<usize>() % indexes.len());
+            self.even_odd_clue
+                .push((index, cells[index].available[0] & 1 == 0));
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.even_odd_clue
+            .iter()
+            .map(|(index, is_even)| Clue::Cell {
+                index: *index,
+                label: if *is_even { "E" } else { "O" },
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "EvenOddRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.even_odd_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "EvenOddRule".to_owned();
+        for (index, is_even) in &self.even_odd_clue {
+            psf += &format!(" ; {index},{}", if *is_even { 'E' } else { 'O' });
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::EvenOddRule {
+            even_odd_clue: self.even_odd_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn even_odd_multi_remove_test() {
+    let even_odd_rule = EvenOddRule {
+        even_odd_clue: vec![(0, true), (5, false)],
+    };
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            even_odd_rule.boxed_clone(),
+        ],
+    );
+    let mut big_buffer = vec![];
+
+    let res = even_odd_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(1, 0), (3, 0), (2, 5), (4, 5)].as_slice());
+
+    // a placed digit with the wrong parity is removed as well
+    sudoku.set_cell(3, 0).unwrap();
+    let res = even_odd_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(3, 0), (2, 5), (4, 5)].as_slice());
+}
+
+#[test]
+fn even_odd_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut even_odd_rule = EvenOddRule {
+        even_odd_clue: vec![],
+    };
+
+    even_odd_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    assert_eq!(even_odd_rule.no_of_clues(), 4);
+    assert!(even_odd_rule.finished_legal(&sudoku));
+}
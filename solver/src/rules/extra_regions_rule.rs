@@ -0,0 +1,388 @@
+// This file is all the logic and tests pertaining to the Extra regions rule
+
+use super::{box_of, clue::Clue, psf_list, DynRule, Rule};
+use bumpalo::Bump;
+use integer_sqrt::IntegerSquareRoot;
+use std::fmt::Debug;
+
+use crate::sudoku::Sudoku;
+
+// Well known sets of extra regions, that depend on the size of the sudoku
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegionPreset {
+    // The extra boxes of a Windoku, placed one cell in from the edges with one cell between them
+    Windoku,
+    // The cells with the same position in every box
+    DisjointGroups,
+}
+
+impl RegionPreset {
+    pub fn regions(&self, size: usize) -> Vec<Vec<usize>> {
+        let sub_s = size.integer_sqrt();
+        match self {
+            RegionPreset::Windoku => {
+                let starts: Vec<usize> = (0..sub_s - 1).map(|i| 1 + i * (sub_s + 1)).collect();
+                starts
+                    .iter()
+                    .flat_map(|y| starts.iter().map(move |x| (*x, *y)))
+                    .map(|(x, y)| {
+                        (0..size)
+                            .map(|i| (y + i / sub_s) * size + x + i % sub_s)
+                            .collect()
+                    })
+                    .collect()
+            }
+            RegionPreset::DisjointGroups => (0..size)
+                .map(|position| {
+                    (0..size)
+                        .map(|square| {
+                            (square / sub_s * sub_s + position / sub_s) * size
+                                + square % sub_s * sub_s
+                                + position % sub_s
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionPreset::Windoku => "windoku",
+            RegionPreset::DisjointGroups => "disjoint",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtraRegionsRule {
+    // Vector of custom regions contaning indexes, where every digit must be different
+    pub extra_regions_clue: Vec<Vec<usize>>,
+    // The presets are turned into regions, when the rule is added to a sudoku and the size is known
+    pub presets: Vec<RegionPreset>,
+    // The custom regions and the regions of the presets
+    regions: Vec<Vec<usize>>,
+    // For every region, the other regions it shares at least two cells with.
+    // Only those can have locked candidates between them, the rest would be hidden singles.
+    overlapping: Vec<Vec<usize>>,
+}
+
+impl ExtraRegionsRule {
+    pub fn new(extra_regions_clue: Vec<Vec<usize>>, presets: Vec<RegionPreset>) -> DynRule {
+        let mut rule = ExtraRegionsRule {
+            regions: vec![],
+            overlapping: vec![],
+            extra_regions_clue,
+            presets,
+        };
+        rule.set_regions(rule.extra_regions_clue.clone());
+        DynRule(Box::new(rule))
+    }
+
+    fn set_regions(&mut self, regions: Vec<Vec<usize>>) {
+        self.overlapping = regions
+            .iter()
+            .enumerate()
+            .map(|(region_index, region)| {
+                regions
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_index, other)| {
+                        *other_index != region_index
+                            && other.iter().filter(|index| region.contains(index)).count() >= 2
+                    })
+                    .map(|(other_index, _)| other_index)
+                    .collect()
+            })
+            .collect();
+        self.regions = regions;
+    }
+
+    pub fn windoku() -> DynRule {
+        Self::new(vec![], vec![RegionPreset::Windoku])
+    }
+
+    pub fn disjoint_groups() -> DynRule {
+        Self::new(vec![], vec![RegionPreset::DisjointGroups])
+    }
+}
+
+// Returns the rows, columns and boxes of the sudoku.
+// The rows of a layout canvas are not houses, so a layout has none.
+fn standard_houses(sudoku: &Sudoku) -> Vec<Vec<usize>> {
+    let (size, has_square) = (sudoku.size, sudoku.has_square);
+    if sudoku.layout.is_some() {
+        return vec![];
+    }
+    let mut houses: Vec<Vec<usize>> = (0..size)
+        .map(|y| (0..size).map(|x| y * size + x).collect())
+        .chain((0..size).map(|x| (0..size).map(|y| y * size + x).collect()))
+        .collect();
+    if has_square {
+        houses.extend((0..size).map(|square| {
+            (0..size * size)
+                .filter(|index| box_of(size, size, *index) == square)
+                .collect()
+        }));
+    }
+    houses
+}
+
+// If every place for the value in a house with all the digits is also in the other house,
+// the value is pushed to the buffer for the rest of the other house
+fn pointing(
+    sudoku: &Sudoku,
+    value: u16,
+    house: &[usize],
+    other: &[usize],
+    buffer: &mut Vec<usize>,
+) -> bool {
+    if house.len() != sudoku.size
+        || house
+            .iter()
+            .any(|index| sudoku.cells[*index].is_single_eq(value))
+    {
+        return false;
+    }
+
+    let is_place = |index: &usize| {
+        !sudoku.cells[*index].locked_in && sudoku.cells[*index].available.contains(&value)
+    };
+    let mut places = house.iter().filter(|index| is_place(index)).peekable();
+    if places.peek().is_none() || !places.all(|index| other.contains(index)) {
+        return false;
+    }
+
+    buffer.clear();
+    buffer.extend(
+        other
+            .iter()
+            .filter(|index| !house.contains(index) && is_place(index)),
+    );
+    !buffer.is_empty()
+}
+
+impl Rule for ExtraRegionsRule {
+    // Returns all indexes in the regions the index is part of
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        for region in &self.regions {
+            if region.contains(&index) {
+                buffer.extend(region);
+            }
+        }
+        buffer
+    }
+
+    fn set_size(&mut self, size: usize) {
+        let mut regions = self.extra_regions_clue.clone();
+        for preset in &self.presets {
+            regions.extend(preset.regions(size));
+        }
+        self.set_regions(regions);
+    }
+
+    // Finds a digit that only has one place left in a region with all the digits.
+    // Smaller custom regions do not need to contain every digit, so they have no hidden singles.
+    fn hidden_singles(&self, sudoku: &Sudoku) -> Option<(u16, usize)> {
+        for region in self.regions.iter().filter(|r| r.len() == sudoku.size) {
+            'value: for value in 1..=sudoku.size as u16 {
+                let mut found_position = None;
+                for index in region {
+                    if sudoku.cells[*index].available.contains(&value) {
+                        if found_position.is_some() {
+                            continue 'value;
+                        }
+                        found_position = Some(*index);
+                    }
+                }
+                if let Some(position) = found_position {
+                    if !sudoku.cells[position].locked_in {
+                        return Some((value, position));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Locked candidates between the regions and the rows, columns, boxes and other regions.
+    // If all places for a digit in one house are inside another house,
+    // the digit can be removed from the rest of the other house.
+    fn locked_candidate<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        buffer: &'buf mut Vec<usize>,
+        _arena: &mut Bump,
+    ) -> Option<(u16, &'buf [usize])> {
+        let houses = standard_houses(sudoku);
+
+        for (region_index, region) in self.regions.iter().enumerate() {
+            let others = houses.iter().chain(
+                self.overlapping[region_index]
+                    .iter()
+                    .map(|other_index| &self.regions[*other_index]),
+            );
+            for other in others {
+                for value in 1..=sudoku.size as u16 {
+                    if pointing(sudoku, value, region, other, buffer)
+                        || pointing(sudoku, value, other, region, buffer)
+                    {
+                        return Some((value, buffer));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.regions.iter().all(|region| {
+            region.iter().enumerate().all(|(i, index)| {
+                region[i + 1..].iter().all(|other| {
+                    sudoku.cells[*index].available[0] != sudoku.cells[*other].available[0]
+                })
+            })
+        })
+    }
+
+    // The regions of the presets are included, once the size is known
+    fn clues(&self) -> Vec<Clue> {
+        self.regions
+            .iter()
+            .map(|region| Clue::Region {
+                cells: region.clone(),
+            })
+            .collect()
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ExtraRegionsRule"
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ExtraRegionsRule".to_owned();
+        for preset in &self.presets {
+            psf += &format!(" ; {}", preset.name());
+        }
+        for region in &self.extra_regions_clue {
+            psf += &format!(" ;{}", psf_list(region));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ExtraRegionsRule {
+            extra_regions_clue: self.extra_regions_clue.clone(),
+            presets: self.presets.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn extra_regions_presets_test() {
+    let windoku = RegionPreset::Windoku.regions(9);
+    assert_eq!(windoku.len(), 4);
+    assert_eq!(windoku[0], vec![10, 11, 12, 19, 20, 21, 28, 29, 30]);
+    assert_eq!(windoku[3], vec![50, 51, 52, 59, 60, 61, 68, 69, 70]);
+
+    let disjoint = RegionPreset::DisjointGroups.regions(4);
+    assert_eq!(
+        disjoint,
+        vec![
+            vec![0, 2, 8, 10],
+            vec![1, 3, 9, 11],
+            vec![4, 6, 12, 14],
+            vec![5, 7, 13, 15]
+        ]
+    );
+}
+
+#[test]
+fn extra_regions_updates_test() {
+    let sudoku = Sudoku::new(9, vec![ExtraRegionsRule::windoku()]);
+    let rule = sudoku
+        .rules
+        .iter()
+        .find(|r| r.get_name() == "ExtraRegionsRule")
+        .unwrap();
+
+    let mut buffer = vec![];
+    assert_eq!(
+        rule.updates(sudoku.size, 10, &mut buffer),
+        vec![10, 11, 12, 19, 20, 21, 28, 29, 30]
+    );
+    assert_eq!(
+        rule.updates(sudoku.size, 0, &mut buffer),
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn extra_regions_hidden_test() {
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![ExtraRegionsRule::new(vec![vec![0, 1, 4, 5]], vec![])],
+    );
+    let rule = sudoku
+        .rules
+        .iter()
+        .find(|r| r.get_name() == "ExtraRegionsRule")
+        .unwrap()
+        .clone();
+
+    for index in [0, 1, 4] {
+        sudoku.cells[index].available.retain(|value| *value != 3);
+    }
+    assert_eq!(rule.hidden_singles(&sudoku), Some((3, 5)));
+}
+
+#[test]
+fn extra_regions_locked_candidate_test() {
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![ExtraRegionsRule::new(vec![vec![1, 2, 5, 6]], vec![])],
+    );
+    let rule = sudoku
+        .rules
+        .iter()
+        .find(|r| r.get_name() == "ExtraRegionsRule")
+        .unwrap()
+        .clone();
+    let mut buffer = vec![];
+    let mut arena = Bump::new();
+
+    // the 2 can only be in the top row of the region, so it is removed from the rest of the row
+    sudoku.cells[5].available.retain(|value| *value != 2);
+    sudoku.cells[6].available.retain(|value| *value != 2);
+    let res = rule.locked_candidate(&sudoku, &mut buffer, &mut arena);
+    assert_eq!(res, Some((2, vec![0, 3].as_slice())));
+}
+
+#[test]
+fn extra_regions_disjoint_solve_test() {
+    let mut sudoku: Sudoku = "SquareRule | ExtraRegionsRule ; disjoint\n\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0"
+        .parse()
+        .unwrap();
+
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&sudoku)));
+}
@@ -0,0 +1,235 @@
+// This file is all the logic and tests pertaining to the Inequality rule
+
+use super::{active_pairs, clue::Clue, DynRule, Rule};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct InequalityRule {
+    // touples of (smaller index, larger index), where the digit in the first cell is less than the digit in the second.
+    // The sign between the cells points to the smaller digit.
+    pub inequality_clue: Vec<(usize, usize)>,
+}
+
+impl InequalityRule {
+    pub fn new(inequality_clue: Vec<(usize, usize)>) -> DynRule {
+        DynRule(Box::new(InequalityRule { inequality_clue }))
+    }
+}
+
+impl Rule for InequalityRule {
+    // An inequality does not affect any other cells
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // Every cell gets a lowest and highest possible digit from its candidates.
+    // The bounds are passed along the chains of inequalities, so in a < b < c the c is at least 3,
+    // and every candidate outside the bounds is removed.
+    // Placed digits are checked as well, so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+
+        let mut low: Vec<u16> = sudoku
+            .cells
+            .iter()
+            .map(|cell| cell.available.iter().min().copied().unwrap_or(0))
+            .collect();
+        let mut high: Vec<u16> = sudoku
+            .cells
+            .iter()
+            .map(|cell| cell.available.iter().max().copied().unwrap_or(0))
+            .collect();
+
+        // A chain is never longer than the number of clues, unless the clues contain a loop that can not be solved
+        for _ in 0..=self.inequality_clue.len() {
+            let mut changed = false;
+            for (smaller, larger) in &self.inequality_clue {
+                if low[*larger] <= low[*smaller] {
+                    low[*larger] = low[*smaller] + 1;
+                    changed = true;
+                }
+                if high[*smaller] >= high[*larger] && high[*smaller] > 0 {
+                    high[*smaller] = high[*larger].saturating_sub(1);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (index, cell) in sudoku.cells.iter().enumerate() {
+            for value in &cell.available {
+                if *value < low[index] || *value > high[index] {
+                    big_buffer.push((*value, index));
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.inequality_clue.iter().all(|(smaller, larger)| {
+            sudoku.cells[*smaller].available[0] < sudoku.cells[*larger].available[0]
+        })
+    }
+
+    // Picks pairs of adjacent cells in the solved sudoku.
+    // Digits close to eachother tell more than digits far apart, since 1 < 9 is almost always true,
+    // so the pairs with the smallest difference are chosen first.
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        let mut pairs: Vec<(u16, usize, (usize, usize))> = active_pairs(cells, width)
+            .filter(|(left, right)| {
+                !self
+                    .inequality_clue
+                    .iter()
+                    .any(|(a, b)| (a == left && b == right) || (a == right && b == left))
+            })
+            .map(|(left, right)| {
+                let (left_value, right_value) =
+                    (cells[left].available[0], cells[right].available[0]);
+                let pair = if left_value < right_value {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                (left_value.abs_diff(right_value), random::<usize>(), pair)
+            })
+            .collect();
+        pairs.sort();
+
+        self.inequality_clue
+            .extend(pairs.iter().take(size * 3 / 2).map(|(_, _, pair)| *pair));
+    }
+
+    // The cells are listed with the smaller digit first
+    fn clues(&self) -> Vec<Clue> {
+        self.inequality_clue
+            .iter()
+            .map(|(left, right)| Clue::Domino {
+                cells: (*left, *right),
+                value: None,
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "InequalityRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.inequality_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "InequalityRule".to_owned();
+        for (smaller, larger) in &self.inequality_clue {
+            psf += &format!(" ; {smaller},{larger}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::InequalityRule {
+            inequality_clue: self.inequality_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn inequality_chain_test() {
+    let inequality_rule = InequalityRule {
+        inequality_clue: vec![(0, 1), (1, 2), (2, 3)],
+    };
+    let sudoku = Sudoku::new(4, vec![]);
+    let mut big_buffer = vec![];
+
+    // a chain of four rising cells on a 4x4 can only be 1, 2, 3, 4
+    let res = inequality_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        res,
+        vec![
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (1, 1),
+            (3, 1),
+            (4, 1),
+            (1, 2),
+            (2, 2),
+            (4, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3)
+        ]
+        .as_slice()
+    );
+}
+
+#[test]
+fn inequality_multi_remove_test() {
+    let inequality_rule = InequalityRule {
+        inequality_clue: vec![(0, 1)],
+    };
+    let mut sudoku = Sudoku::new(9, vec![]);
+    let mut big_buffer = vec![];
+
+    let res = inequality_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(9, 0), (1, 1)].as_slice());
+
+    // the 5 is removed from the row, so only 1 to 4 is left in the smaller cell
+    sudoku.set_cell(5, 1).unwrap();
+    let res = inequality_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(6, 0), (7, 0), (8, 0), (9, 0)].as_slice());
+}
+
+#[test]
+fn inequality_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut inequality_rule = InequalityRule {
+        inequality_clue: vec![],
+    };
+
+    inequality_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    assert_eq!(inequality_rule.no_of_clues(), 6);
+    assert!(inequality_rule
+        .inequality_clue
+        .iter()
+        .all(|(smaller, larger)| sudoku.cells[*smaller].available[0] + 1
+            == sudoku.cells[*larger].available[0]));
+    assert!(inequality_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn inequality_parse_test() {
+    let rule: DynRule = "InequalityRule ; 0,1 ; 10,1".parse().unwrap();
+    assert_eq!(rule.get_name(), "InequalityRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
@@ -92,9 +92,14 @@ impl Rule for KnightRule {
     fn get_name(&self) -> &'static str {
         "KnightRule"
     }
-    fn print_self(&self) -> bool {
-        print!("KnightsMove");
-        true
+    fn to_psf(&self) -> Option<String> {
+        Some("KnightsMove".to_owned())
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::KnightRule
     }
 }
 
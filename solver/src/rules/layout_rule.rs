@@ -0,0 +1,228 @@
+// This file is all the logic and tests pertaining to the Layout rule
+
+use super::{extra_regions_rule::ExtraRegionsRule, DynRule, Rule};
+use bumpalo::Bump;
+use integer_sqrt::IntegerSquareRoot;
+use std::fmt::Debug;
+
+use crate::sudoku::Sudoku;
+
+// Several overlapping grids placed on a larger canvas, like a Samurai sudoku.
+// Every grid has its own rows, columns and boxes, and the cells outside all grids are not used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Layout {
+    // The number of digits, and the width and height of every grid
+    pub size: usize,
+    // The top left corner (x, y) of every grid on the canvas
+    pub grids: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    pub fn new(size: usize, grids: Vec<(usize, usize)>) -> Self {
+        Self { size, grids }
+    }
+
+    // Five grids, where the middle grid shares a box with each of the corner grids
+    pub fn samurai(size: usize) -> Self {
+        let sub_s = size.integer_sqrt();
+        let middle = size - sub_s;
+        let far = 2 * (size - sub_s);
+        Self::new(
+            size,
+            vec![(0, 0), (far, 0), (middle, middle), (0, far), (far, far)],
+        )
+    }
+
+    // Two grids sharing a box in the corner
+    pub fn twodoku(size: usize) -> Self {
+        let sub_s = size.integer_sqrt();
+        Self::new(size, vec![(0, 0), (size - sub_s, size - sub_s)])
+    }
+
+    // Four grids, each moved one box from the others
+    pub fn butterfly(size: usize) -> Self {
+        let sub_s = size.integer_sqrt();
+        Self::new(size, vec![(0, 0), (sub_s, 0), (0, sub_s), (sub_s, sub_s)])
+    }
+
+    pub fn width(&self) -> usize {
+        self.grids
+            .iter()
+            .map(|(x, _)| x + self.size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.grids
+            .iter()
+            .map(|(_, y)| y + self.size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Returns true if the index on the canvas is inside at least one grid
+    pub fn is_active(&self, index: usize) -> bool {
+        let (x, y) = (index % self.width(), index / self.width());
+        self.grids.iter().any(|(grid_x, grid_y)| {
+            (*grid_x..grid_x + self.size).contains(&x) && (*grid_y..grid_y + self.size).contains(&y)
+        })
+    }
+
+    // Returns the rows, columns and boxes of every grid as indexes on the canvas.
+    // A house shared by two grids, like the corner box of a Samurai, is only returned once.
+    pub fn houses(&self) -> Vec<Vec<usize>> {
+        let size = self.size;
+        let sub_s = size.integer_sqrt();
+        let width = self.width();
+
+        let mut houses: Vec<Vec<usize>> = vec![];
+        for (grid_x, grid_y) in &self.grids {
+            let canvas = |x: usize, y: usize| (grid_y + y) * width + grid_x + x;
+            houses.extend((0..size).map(|y| (0..size).map(|x| canvas(x, y)).collect()));
+            houses.extend((0..size).map(|x| (0..size).map(|y| canvas(x, y)).collect()));
+            houses.extend((0..size).map(|square| {
+                (0..size)
+                    .map(|i| {
+                        canvas(
+                            square % sub_s * sub_s + i % sub_s,
+                            square / sub_s * sub_s + i / sub_s,
+                        )
+                    })
+                    .collect()
+            }));
+        }
+
+        for house in &mut houses {
+            house.sort();
+        }
+        houses.sort();
+        houses.dedup();
+        houses
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutRule {
+    pub layout: Layout,
+    // The houses of every grid, which work like extra regions.
+    // Other rules look at the rows and columns of the whole canvas, so only rules where supports_layout is true work with a layout.
+    houses: DynRule,
+}
+
+impl LayoutRule {
+    pub fn new(layout: Layout) -> DynRule {
+        DynRule(Box::new(LayoutRule {
+            houses: ExtraRegionsRule::new(layout.houses(), vec![]),
+            layout,
+        }))
+    }
+}
+
+impl Rule for LayoutRule {
+    fn updates<'buf>(
+        &self,
+        size: usize,
+        index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        self.houses.updates(size, index, buffer)
+    }
+
+    fn hidden_singles(&self, sudoku: &Sudoku) -> Option<(u16, usize)> {
+        self.houses.hidden_singles(sudoku)
+    }
+
+    fn locked_candidate<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        buffer: &'buf mut Vec<usize>,
+        arena: &mut Bump,
+    ) -> Option<(u16, &'buf [usize])> {
+        self.houses.locked_candidate(sudoku, buffer, arena)
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.houses.finished_legal(sudoku)
+    }
+
+    fn layout(&self) -> Option<&Layout> {
+        Some(&self.layout)
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "LayoutRule"
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = format!("LayoutRule ; {}", self.layout.size);
+        for (x, y) in &self.layout.grids {
+            psf += &format!(" ; {x},{y}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::LayoutRule {
+            layout: self.layout.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn layout_presets_test() {
+    let samurai = Layout::samurai(9);
+    assert_eq!((samurai.width(), samurai.height()), (21, 21));
+    assert_eq!(
+        (0..21 * 21)
+            .filter(|index| samurai.is_active(*index))
+            .count(),
+        369
+    );
+    assert!(!samurai.is_active(9));
+    assert!(samurai.is_active(6 * 21 + 9));
+
+    let twodoku = Layout::twodoku(9);
+    assert_eq!((twodoku.width(), twodoku.height()), (15, 15));
+
+    let butterfly = Layout::butterfly(9);
+    assert_eq!((butterfly.width(), butterfly.height()), (12, 12));
+}
+
+#[test]
+fn layout_houses_test() {
+    // each grid has 27 houses, and the four shared corner boxes are only counted once
+    assert_eq!(Layout::samurai(9).houses().len(), 5 * 27 - 4);
+
+    let houses = Layout::twodoku(4).houses();
+    assert_eq!(houses.len(), 2 * 12 - 1);
+    assert!(houses.contains(&vec![14, 15, 20, 21]));
+    assert!(houses.contains(&vec![14, 15, 16, 17]));
+}
+
+#[test]
+fn layout_updates_test() {
+    let sudoku = Sudoku::new(4, vec![LayoutRule::new(Layout::twodoku(4))]);
+    let mut buffer = vec![];
+
+    // the shared cell sees the row, column and box of both grids
+    let mut seen = sudoku.rules[0]
+        .updates(sudoku.size, 14, &mut buffer)
+        .to_vec();
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen, vec![2, 8, 12, 13, 14, 15, 16, 17, 20, 21, 26, 32]);
+}
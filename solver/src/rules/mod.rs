@@ -1,15 +1,11 @@
-use crate::rules::consecutive_rule::ConsecutiveRule;
-use crate::rules::knight_rule::KnightRule;
-use crate::rules::parity_rule::ParityRule;
-use crate::rules::square_rule::SquareRule;
-use crate::rules::thermometer_rule::ThermometerRule;
-use crate::rules::x_rule::XRule;
-use crate::rules::zipper_rule::ZipperRule;
-use crate::{rules::diagonal_rule::DiagonalRule, sudoku::Cell};
+use crate::rules::clue::Clue;
+use crate::rules::layout_rule::Layout;
+use crate::sudoku::Cell;
 use bumpalo::Bump;
+use integer_sqrt::IntegerSquareRoot;
 use std::hash::Hash;
 use std::{
-    fmt::Debug,
+    fmt::{Debug, Display},
     ops::{Deref, DerefMut},
     str::FromStr,
 };
@@ -20,13 +16,29 @@ use crate::sudoku::Sudoku;
 
 // use self::zipper_rule::ZipperRule;
 
+pub mod between_line_rule;
+pub mod clue;
 pub mod column_rule;
 pub mod consecutive_rule;
+pub mod constraint_rule;
 pub mod diagonal_rule;
+pub mod difference_dot_rule;
+pub mod even_odd_rule;
+pub mod extra_regions_rule;
+pub mod inequality_rule;
 pub mod knight_rule;
+pub mod layout_rule;
+pub mod non_consecutive_rule;
 pub mod parity_rule;
+pub mod quadruple_rule;
+pub mod region_sum_line_rule;
+pub mod registry;
 pub mod row_rule;
+#[cfg(feature = "serde")]
+pub mod rule_data;
+pub mod skyscraper_rule;
 pub mod square_rule;
+pub mod sum_dot_rule;
 pub mod thermometer_rule;
 pub mod x_rule;
 pub mod zipper_rule;
@@ -68,36 +80,37 @@ pub trait Rule: Debug {
         return 0;
     }
 
-    fn to_x_rule(&mut self) -> Option<&mut XRule> {
-        None
+    // Describes the clues of the rule, so they can be drawn without knowing the type of the rule
+    fn clues(&self) -> Vec<Clue> {
+        vec![]
     }
 
-    fn to_parity_rule(&mut self) -> Option<&mut ParityRule> {
-        None
+    fn needs_square_for_locked(&self) -> bool {
+        false
     }
 
-    fn to_zipper_rule(&mut self) -> Option<&mut ZipperRule> {
-        None
+    fn priority(&self) -> ExecutionPriority {
+        ExecutionPriority::Medium
     }
 
-    fn to_thermometer_rule(&mut self) -> Option<&mut ThermometerRule> {
-        None
-    }
+    // Makes clues that hold for the solved cells. The width is the length of a row of cells,
+    // which is wider than the size on the canvas of a layout, where the cells outside the grids are 0.
+    fn create_clue(&mut self, _cells: &Vec<Cell>, _size: usize, _width: usize) {}
 
-    fn to_consecutive_rule(&mut self) -> Option<&mut ConsecutiveRule> {
-        None
-    }
+    // Called when the rule is added to a sudoku, for rules where the clue depends on the size
+    fn set_size(&mut self, _size: usize) {}
 
-    fn needs_square_for_locked(&self) -> bool {
+    // Rules that only look at the cells of their clues work on the canvas of a layout.
+    // Rules that look at the rows, columns or boxes of a single grid do not, so a sudoku with a layout refuses them.
+    fn supports_layout(&self) -> bool {
         false
     }
 
-    fn priority(&self) -> ExecutionPriority {
-        ExecutionPriority::Medium
+    // A rule with a layout places several grids on a larger canvas, instead of the single grid with rows and columns
+    fn layout(&self) -> Option<&Layout> {
+        None
     }
 
-    fn create_clue(&mut self, _cells: &Vec<Cell>, _size: usize) {}
-
     fn multi_remove<'buf>(
         &self,
         _sudoku: &Sudoku,
@@ -111,8 +124,28 @@ pub trait Rule: Debug {
         true
     }
 
-    fn print_self(&self) -> bool {
-        false
+    // Checks that the clues fit a grid of width by height cells with the digits up to size,
+    // before any of them are used by the solver
+    fn validate(&self, size: usize, width: usize, height: usize) -> Result<(), String> {
+        self.clues()
+            .iter()
+            .try_for_each(|clue| clue.validate(size, width, height))
+            .map_err(|e| format!("{}: {e}", self.get_name()))
+    }
+
+    // Writes the rule and its clues as they are written in the header of the PSF.
+    // The rows and columns are part of every sudoku, so they are not written.
+    fn to_psf(&self) -> Option<String> {
+        None
+    }
+
+    // The rule and its clues as data that can be serialized.
+    // A rule without its own representation is stored as it is written in the PSF.
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> rule_data::RuleData {
+        rule_data::RuleData::Custom {
+            psf: self.to_psf().unwrap_or_else(|| self.get_name().to_owned()),
+        }
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -122,9 +155,118 @@ pub enum ExecutionPriority {
     Low = 2,
 }
 
+// Returns the up to four orthogonally adjacent indexes of a cell, on a canvas of width by height cells
+pub fn orthogonal_neighbours(
+    width: usize,
+    height: usize,
+    index: usize,
+) -> impl Iterator<Item = usize> {
+    let x = index % width;
+    let y = index / width;
+    [
+        (y > 0).then(|| index - width),
+        (x > 0).then(|| index - 1),
+        (x + 1 < width).then(|| index + 1),
+        (y + 1 < height).then(|| index + width),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+// Returns the box of the index, with the same layout as the SquareRule.
+// On the canvas of a layout the boxes continue past the first grid, so the grids must start on a box.
+pub fn box_of(size: usize, width: usize, index: usize) -> usize {
+    let sub_s = size.integer_sqrt();
+    (index / width / sub_s) * width.div_ceil(sub_s) + (index % width) / sub_s
+}
+
+// Returns the up to eight indexes a king could move to, which are the neighbours a line can continue to
+pub fn king_neighbours(width: usize, height: usize, index: usize) -> impl Iterator<Item = usize> {
+    let x = (index % width) as isize;
+    let y = (index / width) as isize;
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+        .filter(move |(nx, ny)| {
+            (*nx, *ny) != (x, y)
+                && (0..width as isize).contains(nx)
+                && (0..height as isize).contains(ny)
+        })
+        .map(move |(nx, ny)| ny as usize * width + nx as usize)
+}
+
+// Returns every pair of orthogonally adjacent indexes once, with the lowest index first
+pub fn orthogonal_pairs(width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..width * height).flat_map(move |index| {
+        orthogonal_neighbours(width, height, index)
+            .filter(move |neighbour| *neighbour > index)
+            .map(move |neighbour| (index, neighbour))
+    })
+}
+
+// Returns the orthogonal pairs of cells, where both cells are inside the grids of a layout.
+// The cells outside the grids are locked to 0.
+pub fn active_pairs(cells: &[Cell], width: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    orthogonal_pairs(width, cells.len() / width)
+        .filter(|(left, right)| !cells[*left].is_single_eq(0) && !cells[*right].is_single_eq(0))
+}
+
+// The number of dots a generated dot rule keeps, when not every dot has to be given.
+// Around two dots per row leaves enough to guide the solver, while the removed cells
+// still have to be found from the other rules.
+pub fn dot_clue_limit(size: usize) -> usize {
+    size * 2
+}
+
+// Writes the values separated by commas, as the clue lists are written in the PSF
+pub fn psf_list(values: &[impl Display]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Returns true when a rule puts the two cells in the same house, so they can never be equal.
+// Adjacent cells of a single grid always share a row or column, but two grids of a layout can touch without overlapping.
+pub fn share_house(sudoku: &Sudoku, left: usize, right: usize, buffer: &mut Vec<usize>) -> bool {
+    sudoku.layout.is_none()
+        || sudoku
+            .rules
+            .iter()
+            .any(|rule| rule.updates(sudoku.size, left, buffer).contains(&right))
+}
+
+// Pushes the candidates of other, that can not be next to any candidate of cell in a domino rule.
+// When the cells are distinct, a candidate is not supported by the same digit in cell.
+pub fn push_unsupported(
+    cell: &Cell,
+    other: &Cell,
+    other_index: usize,
+    distinct: bool,
+    allowed: impl Fn(u16, u16) -> bool,
+    big_buffer: &mut Vec<(u16, usize)>,
+) {
+    for value in &other.available {
+        if !cell
+            .available
+            .iter()
+            .any(|c| !(distinct && *c == *value) && allowed(*c, *value))
+        {
+            big_buffer.push((*value, other_index));
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DynRule(Box<dyn Rule + Send>);
 
+impl DynRule {
+    // Wraps a rule, so rules from other crates can be used in a sudoku
+    pub fn new(rule: impl Rule + Send + 'static) -> Self {
+        DynRule(Box::new(rule))
+    }
+}
+
 impl Hash for DynRule {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.get_name().hash(state);
@@ -153,91 +295,8 @@ impl DerefMut for DynRule {
 impl FromStr for DynRule {
     type Err = String;
 
+    // Looks the rule up in the registry, so rules registered by other crates can be parsed as well
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "SquareRule" => Ok(SquareRule::new()),
-            "KnightsMove" => Ok(KnightRule::new()),
-            "DiagonalRule" => Ok(DiagonalRule::new()),
-            _ => {
-                let mut rule_params = s.split(';').map(str::trim);
-                match rule_params.next() {
-                    Some("XRule") => Ok(DynRule(Box::new(XRule {
-                        x_clue: rule_params
-                            .map(|s| {
-                                let Some((l, r)) = s.split_once(',') else {
-                                    return Err(format!("Failed to split {s} on ,"));
-                                };
-                                let l = l.parse().map_err(|e| format!("{e:?}"))?;
-                                let r = r.parse().map_err(|e| format!("{e:?}"))?;
-
-                                Ok((l, r))
-                            })
-                            .collect::<Result<_, _>>()?,
-                    }))),
-                    Some("ThermometerRule") => Ok(DynRule(Box::new(ThermometerRule {
-                        themometer_clue: rule_params
-                            .map(|ther| {
-                                ther.split(',')
-                                    .map(|index| index.parse().map_err(|e| format!("{e:?}")))
-                                    .collect::<Result<Vec<_>, _>>()
-                            })
-                            .collect::<Result<Vec<Vec<usize>>, _>>()?,
-                    }))),
-                    Some("ZipperRule") => Ok(DynRule(Box::new(ZipperRule {
-                        zipper_clue: rule_params
-                            .map(|s| {
-                                let Some((center, rest)) = s.split_once(',') else {
-                                    return Err(format!("Failed to split {s} on ,"));
-                                };
-
-                                let center = center.parse().map_err(|e| format!("{e:?}"))?;
-                                let indecies = rest.split(',').map(str::trim);
-
-                                let rest_resolved = indecies
-                                    .map(|s| {
-                                        let Some((l, r)) = s.split_once('+') else {
-                                            return Err(format!("Failed to split {s} on +"));
-                                        };
-                                        let l = l.parse().map_err(|e| format!("{e:?}"))?;
-                                        let r = r.parse().map_err(|e| format!("{e:?}"))?;
-
-                                        Ok((l, r))
-                                    })
-                                    .collect::<Result<_, _>>()?;
-
-                                Ok((center, rest_resolved))
-                            })
-                            .collect::<Result<_, _>>()?,
-                    }))),
-                    Some("ParityRule") => Ok(DynRule(Box::new(ParityRule {
-                        parity_clue: rule_params
-                            .map(|s| {
-                                let Some((l, r)) = s.split_once(',') else {
-                                    return Err(format!("Failed to split {s} on ,"));
-                                };
-                                let l = l.parse().map_err(|e| format!("{e:?}"))?;
-                                let r = r.parse().map_err(|e| format!("{e:?}"))?;
-
-                                Ok((l, r))
-                            })
-                            .collect::<Result<_, _>>()?,
-                    }))),
-                    Some("ConsecutiveRule") => Ok(DynRule(Box::new(ConsecutiveRule {
-                        consecutive_clue: rule_params
-                            .map(|s| {
-                                let Some((l, r)) = s.split_once(',') else {
-                                    return Err(format!("Failed to split {s} on ,"));
-                                };
-                                let l = l.parse().map_err(|e| format!("{e:?}"))?;
-                                let r = r.parse().map_err(|e| format!("{e:?}"))?;
-
-                                Ok((l, r))
-                            })
-                            .collect::<Result<_, _>>()?,
-                    }))),
-                    _ => return Err(s.to_owned()),
-                }
-            }
-        }
+        registry::parse_rule(s)
     }
 }
@@ -0,0 +1,172 @@
+// This file is all the logic and tests pertaining to the Non-consecutive rule
+
+use super::{orthogonal_neighbours, DynRule, Rule};
+use std::fmt::Debug;
+
+use crate::sudoku::Sudoku;
+
+// Orthogonally adjacent cells may never contain consecutive digits.
+// Unlike the ConsecutiveRule this is a global rule, so it has no clues.
+#[derive(Debug, Clone)]
+pub struct NonConsecutiveRule;
+
+impl NonConsecutiveRule {
+    pub fn new() -> DynRule {
+        DynRule(Box::new(Self))
+    }
+}
+
+impl Rule for NonConsecutiveRule {
+    // Placing a digit never removes the same digit from the neighbours,
+    // so the updates function returns an empty buffer
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // A value can be removed from a cell, if every candidate of one of its neighbours is one away from it.
+    // For a placed digit this removes the digit above and below it from all four neighbours,
+    // and for an unresolved pair like {4,6} it removes the 5 from the neighbours.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+
+        for (index, cell) in sudoku.cells.iter().enumerate() {
+            // Only cells with at most three candidates can be one away from every candidate
+            if cell.available.is_empty() || cell.available.len() > 3 {
+                continue;
+            }
+
+            for neighbour in orthogonal_neighbours(sudoku.size, sudoku.size, index) {
+                let neighbour_cell = &sudoku.cells[neighbour];
+                // Two placed digits next to eachother can only be removed if they are in conflict,
+                // which makes the solver drop the branch right away instead of when the grid is full
+                if neighbour_cell.locked_in && !cell.locked_in {
+                    continue;
+                }
+
+                for value in &neighbour_cell.available {
+                    if cell.available.iter().all(|c| c.abs_diff(*value) <= 1) {
+                        big_buffer.push((*value, neighbour));
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        (0..sudoku.cells.len()).all(|index| {
+            orthogonal_neighbours(sudoku.size, sudoku.size, index).all(|neighbour| {
+                sudoku.cells[index].available[0].abs_diff(sudoku.cells[neighbour].available[0]) != 1
+            })
+        })
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "NonConsecutiveRule"
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        Some("NonConsecutiveRule".to_owned())
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::NonConsecutiveRule
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn non_consecutive_placed_test() {
+    let non_consecutive_rule = NonConsecutiveRule;
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            non_consecutive_rule.boxed_clone(),
+        ],
+    );
+    let mut big_buffer = vec![];
+
+    sudoku.set_cell(2, 5).unwrap();
+    println!("{sudoku}");
+
+    let res = non_consecutive_rule.multi_remove(&sudoku, &mut big_buffer);
+    // 2 is already removed from the neighbours by the row and column, so only 1 and 3 is left to remove
+    assert_eq!(
+        res,
+        vec![
+            (1, 1),
+            (3, 1),
+            (1, 4),
+            (3, 4),
+            (1, 6),
+            (3, 6),
+            (1, 9),
+            (3, 9)
+        ]
+        .as_slice()
+    );
+}
+
+#[test]
+fn non_consecutive_pair_test() {
+    let non_consecutive_rule = NonConsecutiveRule;
+    let mut sudoku = Sudoku::new(9, vec![]);
+    let mut big_buffer = vec![];
+
+    sudoku.cells[10].available = smallvec::smallvec![4, 6];
+
+    let res = non_consecutive_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(5, 1), (5, 9), (5, 11), (5, 19)].as_slice());
+}
+
+#[test]
+fn non_consecutive_pair_fixture() {
+    // both 1 and 3 are next to a 2, so the neighbours of the 13 can not be 2
+    let sudoku = Sudoku::from_candidates(
+        "1234 1234 | 1234 1234
+         1234 13   | 1234 1234
+         ----------+----------
+         1234 1234 | 1234 1234
+         1234 1234 | 1234 1234",
+        vec![],
+    )
+    .unwrap();
+    let mut big_buffer = vec![];
+
+    let res = NonConsecutiveRule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(2, 1), (2, 4), (2, 6), (2, 9)].as_slice());
+}
+
+#[test]
+fn non_consecutive_finished_legal_test() {
+    let non_consecutive_rule = NonConsecutiveRule;
+
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    assert!(!non_consecutive_rule.finished_legal(&sudoku));
+
+    let mut sudoku = Sudoku::new(2, vec![]);
+    sudoku.cells[0] = crate::sudoku::Cell::single(1);
+    sudoku.cells[1] = crate::sudoku::Cell::single(3);
+    sudoku.cells[2] = crate::sudoku::Cell::single(3);
+    sudoku.cells[3] = crate::sudoku::Cell::single(1);
+    assert!(non_consecutive_rule.finished_legal(&sudoku));
+}
@@ -1,6 +1,6 @@
 // This file is all the logic and tests pertaining to the Parity rule
 // Author Peter s224759
-use super::{DynRule, Rule};
+use super::{active_pairs, clue::Clue, DynRule, Rule};
 use crate::sudoku::Sudoku;
 use rand::random;
 use std::fmt::Debug;
@@ -149,30 +149,11 @@ impl Rule for ParityRule {
         big_buffer
     }
 
-    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize) {
-        for index in 0..cells.len() {
-            if let Some(current) = cells[index].available.get(0) {
-                if index + 1 >= cells.len() {
-                    continue;
-                }
-                if let Some(right) = cells[index + 1].available.get(0) {
-                    if ((current & 1) == 0 && (right & 1) != 0)
-                        || ((current & 1) != 0 && (right & 1) == 0)
-                    {
-                        // parity rule should have (current , right)
-                        self.parity_clue.push((index, index + 1));
-                    }
-                }
-                if index + size >= cells.len() {
-                    continue;
-                }
-                if let Some(below) = cells[index + size].available.get(0) {
-                    if (current & 1 == 0 && below & 1 != 0) || (current & 1 != 0 && below & 1 == 0)
-                    {
-                        // parity rule should have (index , below)
-                        self.parity_clue.push((index, index + size));
-                    }
-                }
+    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize, width: usize) {
+        // the cells outside the grids of a layout are 0, so they are left out before comparing the parity
+        for (left, right) in active_pairs(cells, width) {
+            if (cells[left].available[0] & 1) != (cells[right].available[0] & 1) {
+                self.parity_clue.push((left, right));
             }
         }
         let count = self.parity_clue.len();
@@ -183,6 +164,10 @@ impl Rule for ParityRule {
         }
     }
 
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
     fn boxed_clone(&self) -> DynRule {
         DynRule(Box::new(self.clone()))
     }
@@ -191,19 +176,33 @@ impl Rule for ParityRule {
         "ParityRule"
     }
 
-    fn to_parity_rule(&mut self) -> Option<&mut ParityRule> {
-        Some(self)
+    fn clues(&self) -> Vec<Clue> {
+        self.parity_clue
+            .iter()
+            .map(|(left, right)| Clue::Domino {
+                cells: (*left, *right),
+                value: None,
+            })
+            .collect()
     }
 
     fn no_of_clues(&self) -> usize {
         return self.parity_clue.len();
     }
-    fn print_self(&self) -> bool {
-        print!("ParityRule");
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ParityRule".to_owned();
         for (x,y) in &self.parity_clue {
-            print!(" ; {x},{y}")
+            psf += &format!(" ; {x},{y}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ParityRule {
+            parity_clue: self.parity_clue.clone(),
         }
-        true
     }
 }
 
@@ -0,0 +1,259 @@
+// This file is all the logic and tests pertaining to the Quadruple rule
+
+use super::{clue::Clue, psf_list, DynRule, Rule};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct QuadrupleRule {
+    // touples of (corner index, digits). The circle sits on the bottom right corner of the corner index,
+    // and all the digits must appear in the four cells around it. A digit listed twice must appear twice.
+    pub quadruple_clue: Vec<(usize, Vec<u16>)>,
+}
+
+impl QuadrupleRule {
+    pub fn new(quadruple_clue: Vec<(usize, Vec<u16>)>) -> DynRule {
+        DynRule(Box::new(QuadrupleRule { quadruple_clue }))
+    }
+}
+
+// Returns the four cells around the circle at the bottom right corner of the index,
+// where the width is the length of a row, which is wider than the size on the canvas of a layout
+fn quad(width: usize, corner: usize) -> [usize; 4] {
+    [corner, corner + 1, corner + width, corner + width + 1]
+}
+
+// Returns how many times the value is listed in the digits
+fn count(digits: &[u16], value: u16) -> usize {
+    digits.iter().filter(|digit| **digit == value).count()
+}
+
+impl Rule for QuadrupleRule {
+    // A circle does not affect any cells outside the quad
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // If a listed digit only has as many places left in the quad as it is listed, it must go in those places
+    fn hidden_singles(&self, sudoku: &Sudoku) -> Option<(u16, usize)> {
+        for (corner, digits) in &self.quadruple_clue {
+            let cells = quad(sudoku.width(), *corner);
+            for digit in digits {
+                let placed = cells
+                    .iter()
+                    .filter(|index| sudoku.cells[**index].is_single_eq(*digit))
+                    .count();
+                let mut places = cells.iter().filter(|index| {
+                    !sudoku.cells[**index].locked_in
+                        && sudoku.cells[**index].available.contains(digit)
+                });
+
+                if let Some(place) = places.next() {
+                    if placed + 1 + places.count() == count(digits, *digit) {
+                        return Some((*digit, *place));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // When the listed digits that are not placed yet fill the rest of the quad,
+    // every other digit is removed from the cells that are not placed
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+
+        for (corner, digits) in &self.quadruple_clue {
+            let cells = quad(sudoku.width(), *corner);
+            let open: Vec<usize> = cells
+                .into_iter()
+                .filter(|index| !sudoku.cells[*index].locked_in)
+                .collect();
+
+            let mut missing = digits.clone();
+            for index in cells {
+                if sudoku.cells[index].locked_in {
+                    if let Some(position) = missing
+                        .iter()
+                        .position(|digit| sudoku.cells[index].is_single_eq(*digit))
+                    {
+                        missing.remove(position);
+                    }
+                }
+            }
+
+            if missing.is_empty() || missing.len() != open.len() {
+                continue;
+            }
+            for index in open {
+                for value in &sudoku.cells[index].available {
+                    if !missing.contains(value) {
+                        big_buffer.push((*value, index));
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.quadruple_clue.iter().all(|(corner, digits)| {
+            digits.iter().all(|digit| {
+                quad(sudoku.width(), *corner)
+                    .iter()
+                    .filter(|index| sudoku.cells[**index].available[0] == *digit)
+                    .count()
+                    >= count(digits, *digit)
+            })
+        })
+    }
+
+    // Places circles on random corners, listing two to four of the digits around each of them
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        let height = cells.len() / width;
+        // The circles are only placed where all four cells are inside the grids of a layout
+        let mut corners: Vec<usize> = (0..cells.len())
+            .filter(|index| index % width != width - 1 && index / width != height - 1)
+            .filter(|index| {
+                quad(width, *index)
+                    .iter()
+                    .all(|cell| cells[*cell].available[0] != 0)
+            })
+            .filter(|index| !self.quadruple_clue.iter().any(|(i, _)| i == index))
+            .collect();
+
+        for _ in 0..size / 2 {
+            if corners.is_empty() {
+                break;
+            }
+            let corner = corners.remove(random::<usize>() % corners.len());
+
+            let mut quad_digits: Vec<u16> = quad(width, corner)
+                .iter()
+                .map(|index| cells[*index].available[0])
+                .collect();
+            let digit_count = 2 + random::<usize>() % 3;
+            while quad_digits.len() > digit_count {
+                quad_digits.remove(random::<usize>() % quad_digits.len());
+            }
+            quad_digits.sort();
+
+            self.quadruple_clue.push((corner, quad_digits));
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.quadruple_clue
+            .iter()
+            .map(|(corner, digits)| Clue::Corner {
+                index: *corner,
+                digits: digits.clone(),
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "QuadrupleRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.quadruple_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "QuadrupleRule".to_owned();
+        for (corner, digits) in &self.quadruple_clue {
+            psf += &format!(" ; {corner},{}", psf_list(digits));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::QuadrupleRule {
+            quadruple_clue: self.quadruple_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn quadruple_multi_remove_test() {
+    let quadruple_rule = QuadrupleRule {
+        quadruple_clue: vec![(0, vec![1, 2, 3, 4]), (10, vec![1, 2])],
+    };
+    let mut sudoku = Sudoku::new(4, vec![]);
+    let mut big_buffer = vec![];
+
+    // a full quad on a 4x4 does not remove anything, and the pair only fills two cells
+    let res = quadruple_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![].as_slice());
+
+    // with a 3 and a 4 placed, the last two cells must be the 1 and the 2
+    sudoku.set_cell(3, 10).unwrap();
+    sudoku.set_cell(4, 11).unwrap();
+    let res = quadruple_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(4, 14), (3, 15)].as_slice());
+}
+
+#[test]
+fn quadruple_hidden_test() {
+    let quadruple_rule = QuadrupleRule {
+        quadruple_clue: vec![(0, vec![1, 1])],
+    };
+    let mut sudoku = Sudoku::new(4, vec![]);
+
+    assert_eq!(quadruple_rule.hidden_singles(&sudoku), None);
+
+    // the 1 is removed from two of the cells, so the other two must both be 1
+    sudoku.cells[0].available.retain(|value| *value != 1);
+    sudoku.cells[5].available.retain(|value| *value != 1);
+    assert_eq!(quadruple_rule.hidden_singles(&sudoku), Some((1, 1)));
+}
+
+#[test]
+fn quadruple_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut quadruple_rule = QuadrupleRule {
+        quadruple_clue: vec![],
+    };
+
+    quadruple_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    assert_eq!(quadruple_rule.no_of_clues(), 2);
+    assert!(quadruple_rule
+        .quadruple_clue
+        .iter()
+        .all(|(_, digits)| (2..=4).contains(&digits.len())));
+    assert!(quadruple_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn quadruple_parse_test() {
+    let rule: DynRule = "QuadrupleRule ; 0,1,2 ; 30,4,4,5".parse().unwrap();
+    assert_eq!(rule.get_name(), "QuadrupleRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
@@ -0,0 +1,352 @@
+// This file is all the logic and tests pertaining to the Region sum line rule
+
+use super::{box_of, clue::Clue, king_neighbours, psf_list, DynRule, Rule};
+use integer_sqrt::IntegerSquareRoot;
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct RegionSumLineRule {
+    // Vector of lines contaning indexes in the order the line is drawn.
+    // The box borders split a line into segments, and every segment must have the same sum.
+    pub region_sum_clue: Vec<Vec<usize>>,
+}
+
+impl RegionSumLineRule {
+    pub fn new(region_sum_clue: Vec<Vec<usize>>) -> DynRule {
+        DynRule(Box::new(RegionSumLineRule { region_sum_clue }))
+    }
+}
+
+// Splits a line into the segments it has in each box it passes through.
+// A line that leaves a box and comes back later has two segments in that box.
+fn segments(size: usize, line: &[usize]) -> impl Iterator<Item = &[usize]> {
+    line.chunk_by(move |a, b| box_of(size, size, *a) == box_of(size, size, *b))
+}
+
+// Returns the smallest and largest sum the segment can have.
+// All cells of a segment are in the same box, so the digits are different.
+fn sum_range(sudoku: &Sudoku, segment: &[usize]) -> (u16, u16) {
+    let len = segment.len() as u16;
+    let size = sudoku.size as u16;
+
+    let mins = segment
+        .iter()
+        .map(|index| {
+            sudoku.cells[*index]
+                .available
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or(0)
+        })
+        .sum::<u16>();
+    let maxs = segment
+        .iter()
+        .map(|index| {
+            sudoku.cells[*index]
+                .available
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or(0)
+        })
+        .sum::<u16>();
+
+    (
+        mins.max(len * (len + 1) / 2),
+        maxs.min(len * (2 * size + 1 - len) / 2),
+    )
+}
+
+// Searches for a path inside the box of the last cell in the path, that adds the sum up to the target.
+// The budget limits how many paths are tried, since the search can be slow for large sudokus.
+fn extend_segment(
+    cells: &[Cell],
+    size: usize,
+    path: &mut Vec<usize>,
+    sum: u16,
+    target: u16,
+    blocked: &[usize],
+    budget: &mut usize,
+) -> bool {
+    if sum == target {
+        return true;
+    }
+    if sum > target || *budget == 0 {
+        return false;
+    }
+    *budget -= 1;
+
+    let last = path[path.len() - 1];
+    let options: Vec<usize> = king_neighbours(size, size, last)
+        .filter(|index| {
+            box_of(size, size, *index) == box_of(size, size, last)
+                && !blocked.contains(index)
+                && !path.contains(index)
+        })
+        .collect();
+    if options.is_empty() {
+        return false;
+    }
+
+    // Start at a random option, so the lines do not all bend the same way
+    let offset = random::<usize>() % options.len();
+    for i in 0..options.len() {
+        let next = options[(i + offset) % options.len()];
+        path.push(next);
+        if extend_segment(
+            cells,
+            size,
+            path,
+            sum + cells[next].available[0],
+            target,
+            blocked,
+            budget,
+        ) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+impl Rule for RegionSumLineRule {
+    // Digits on a line may repeat across boxes, so placing a digit does not remove it from the line
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // The segments depend on the boxes of the SquareRule
+    fn needs_square_for_locked(&self) -> bool {
+        true
+    }
+
+    // The common sum of a line must be in the sum range of every segment.
+    // A candidate is removed, if the rest of its segment can not bring the sum into that common range.
+    // Placed digits are checked as well, so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+
+        for line in &self.region_sum_clue {
+            let (low, high) = segments(sudoku.size, line)
+                .map(|segment| sum_range(sudoku, segment))
+                .fold((0, u16::MAX), |(low, high), (min, max)| {
+                    (low.max(min), high.min(max))
+                });
+
+            for segment in segments(sudoku.size, line) {
+                for index in segment {
+                    let (rest_min, rest_max) = segment
+                        .iter()
+                        .filter(|other| *other != index)
+                        .map(|other| &sudoku.cells[*other].available)
+                        .fold((0, 0), |(min, max), available| {
+                            (
+                                min + available.iter().min().copied().unwrap_or(0),
+                                max + available.iter().max().copied().unwrap_or(0),
+                            )
+                        });
+
+                    for value in &sudoku.cells[*index].available {
+                        if value + rest_min > high || value + rest_max < low {
+                            big_buffer.push((*value, *index));
+                        }
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.region_sum_clue.iter().all(|line| {
+            let mut sums = segments(sudoku.size, line).map(|segment| {
+                segment
+                    .iter()
+                    .map(|index| sudoku.cells[*index].available[0])
+                    .sum::<u16>()
+            });
+            let first = sums.next();
+            sums.all(|sum| Some(sum) == first)
+        })
+    }
+
+    // Creates lines by walking a short random segment in one box,
+    // and then searching the neighbouring boxes for segments with the same sum.
+    // Only lines that cross at least two boxes are added.
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, _width: usize) {
+        let sub_s = size.integer_sqrt();
+        let mut seen: Vec<usize> = self.region_sum_clue.iter().flatten().copied().collect();
+
+        for _ in 0..size {
+            let start = random::<usize>() % cells.len();
+            if seen.contains(&start) {
+                continue;
+            }
+
+            let mut line = vec![start];
+            let first_len = 1 + random::<usize>() % sub_s;
+            while line.len() < first_len {
+                let options: Vec<usize> = king_neighbours(size, size, line[line.len() - 1])
+                    .filter(|index| {
+                        box_of(size, size, *index) == box_of(size, size, start)
+                            && !seen.contains(index)
+                            && !line.contains(index)
+                    })
+                    .collect();
+                if options.is_empty() {
+                    break;
+                }
+                line.push(options[random::<usize>() % options.len()]);
+            }
+            let target: u16 = line.iter().map(|index| cells[*index].available[0]).sum();
+
+            let segment_count = 2 + random::<usize>() % 2;
+            for _ in 1..segment_count {
+                let last = line[line.len() - 1];
+                let mut budget = size * size;
+                let blocked: Vec<usize> = seen.iter().chain(&line).copied().collect();
+
+                let found = king_neighbours(size, size, last)
+                    .filter(|index| {
+                        box_of(size, size, *index) != box_of(size, size, last)
+                            && !blocked.contains(index)
+                    })
+                    .find_map(|next| {
+                        let mut path = vec![next];
+                        let found = extend_segment(
+                            cells,
+                            size,
+                            &mut path,
+                            cells[next].available[0],
+                            target,
+                            &blocked,
+                            &mut budget,
+                        );
+                        found.then_some(path)
+                    });
+
+                let Some(segment) = found else {
+                    break;
+                };
+                line.extend(segment);
+            }
+
+            if segments(size, &line).count() < 2 {
+                continue;
+            }
+            seen.extend(&line);
+            self.region_sum_clue.push(line);
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.region_sum_clue
+            .iter()
+            .map(|line| Clue::Line {
+                cells: line.clone(),
+                label: None,
+            })
+            .collect()
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "RegionSumLineRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.region_sum_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "RegionSumLineRule".to_owned();
+        for line in &self.region_sum_clue {
+            psf += &format!(" ;{}", psf_list(line));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::RegionSumLineRule {
+            region_sum_clue: self.region_sum_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn region_sum_line_segments_test() {
+    let line = vec![0, 1, 2, 3, 12, 21, 30];
+    let res: Vec<&[usize]> = segments(9, &line).collect();
+    assert_eq!(res, vec![&[0, 1, 2][..], &[3, 12, 21][..], &[30][..]]);
+}
+
+#[test]
+fn region_sum_line_multi_remove_test() {
+    let region_sum_line_rule = RegionSumLineRule {
+        region_sum_clue: vec![vec![0, 1, 2]],
+    };
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            region_sum_line_rule.boxed_clone(),
+        ],
+    );
+    let mut big_buffer = vec![];
+
+    // Two different digits in the first box add to at least 3 and the single cell is at most 4,
+    // so the first box can not have a 4, and the second box can not have a 1 or 2
+    let res = region_sum_line_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(4, 0), (4, 1), (1, 2), (2, 2)].as_slice());
+
+    // With a 1 in the first box, the 1 is gone from the row as well
+    sudoku.set_cell(1, 0).unwrap();
+    let res = region_sum_line_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(4, 1), (2, 2)].as_slice());
+}
+
+#[test]
+fn region_sum_line_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut region_sum_line_rule = RegionSumLineRule {
+        region_sum_clue: vec![],
+    };
+
+    for _ in 0..10 {
+        region_sum_line_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+    }
+
+    assert!(region_sum_line_rule.finished_legal(&sudoku));
+    for line in &region_sum_line_rule.region_sum_clue {
+        assert!(segments(sudoku.size, line).count() >= 2);
+    }
+}
+
+#[test]
+fn region_sum_line_parse_test() {
+    let rule: DynRule = "RegionSumLineRule ; 1,2,3,4 ; 30,31,40".parse().unwrap();
+    assert_eq!(rule.get_name(), "RegionSumLineRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
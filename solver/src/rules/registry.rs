@@ -0,0 +1,569 @@
+// This file is the registry of rules, that maps the rule names in the PSF to their parsers and constructors
+
+use super::between_line_rule::BetweenLineRule;
+use super::consecutive_rule::ConsecutiveRule;
+use super::constraint_rule::{ConstraintRule, Predicate};
+use super::diagonal_rule::DiagonalRule;
+use super::difference_dot_rule::DifferenceDotRule;
+use super::even_odd_rule::EvenOddRule;
+use super::extra_regions_rule::{ExtraRegionsRule, RegionPreset};
+use super::inequality_rule::InequalityRule;
+use super::knight_rule::KnightRule;
+use super::layout_rule::{Layout, LayoutRule};
+use super::non_consecutive_rule::NonConsecutiveRule;
+use super::parity_rule::ParityRule;
+use super::quadruple_rule::QuadrupleRule;
+use super::region_sum_line_rule::RegionSumLineRule;
+use super::skyscraper_rule::SkyscraperRule;
+use super::square_rule::SquareRule;
+use super::sum_dot_rule::SumDotRule;
+use super::thermometer_rule::ThermometerRule;
+use super::x_rule::XRule;
+use super::zipper_rule::ZipperRule;
+use super::DynRule;
+use lazy_static::lazy_static;
+use std::{fmt::Debug, str::FromStr, sync::RwLock};
+
+// A parser gets the parameters after the rule name, split on ; and trimmed
+pub type RuleParser = fn(&[&str]) -> Result<DynRule, String>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RuleEntry {
+    // The name of the rule in the PSF
+    pub name: &'static str,
+    pub parse: RuleParser,
+    // Creates the rule without any clues, so it can make its own when a sudoku is generated.
+    // Rules that can not be made without a clue, like the LayoutRule, have none.
+    pub new: Option<fn() -> DynRule>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RuleRegistry {
+    entries: Vec<RuleEntry>,
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<RuleRegistry> = RwLock::new(RuleRegistry::builtin());
+}
+
+impl RuleRegistry {
+    // A registry with all the rules of this crate
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for entry in builtin_rules() {
+            registry.register(entry);
+        }
+        registry
+    }
+
+    // Adds the rule, or replaces the rule with the same name
+    pub fn register(&mut self, entry: RuleEntry) {
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RuleEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.iter().map(|entry| entry.name)
+    }
+
+    // Parses a rule from the PSF, like "XRule ; 0,1 ; 4,5"
+    pub fn parse(&self, s: &str) -> Result<DynRule, String> {
+        let params: Vec<&str> = s.split(';').map(str::trim).collect();
+        let Some(entry) = self.get(params[0]) else {
+            return Err(s.to_owned());
+        };
+        (entry.parse)(&params[1..])
+    }
+}
+
+// Registers a rule in the registry used when parsing the PSF, so rules from other crates can be parsed
+pub fn register_rule(entry: RuleEntry) {
+    REGISTRY.write().unwrap().register(entry);
+}
+
+pub fn parse_rule(s: &str) -> Result<DynRule, String> {
+    REGISTRY.read().unwrap().parse(s)
+}
+
+// Returns the names of all registered rules
+pub fn rule_names() -> Vec<&'static str> {
+    REGISTRY.read().unwrap().names().collect()
+}
+
+// Creates the registered rule without any clues, for generating a sudoku
+pub fn new_rule(name: &str) -> Option<DynRule> {
+    REGISTRY.read().unwrap().get(name)?.new.map(|new| new())
+}
+
+// Creates a rule to generate a sudoku with. A name alone gives the rule without clues, so it makes its own,
+// while a rule with parameters, like "LayoutRule ; 9 ; samurai", is parsed as in the PSF.
+pub fn generation_rule(s: &str) -> Result<DynRule, String> {
+    let s = s.trim();
+    match new_rule(s) {
+        Some(rule) => Ok(rule),
+        None => parse_rule(s),
+    }
+}
+
+fn builtin_rules() -> Vec<RuleEntry> {
+    vec![
+        RuleEntry {
+            name: "SquareRule",
+            parse: |_| Ok(SquareRule::new()),
+            new: Some(SquareRule::new),
+        },
+        RuleEntry {
+            name: "KnightsMove",
+            parse: |_| Ok(KnightRule::new()),
+            new: Some(KnightRule::new),
+        },
+        RuleEntry {
+            name: "DiagonalRule",
+            parse: |_| Ok(DiagonalRule::new()),
+            new: Some(DiagonalRule::new),
+        },
+        RuleEntry {
+            name: "NonConsecutiveRule",
+            parse: |_| Ok(NonConsecutiveRule::new()),
+            new: Some(NonConsecutiveRule::new),
+        },
+        RuleEntry {
+            name: "XRule",
+            parse: |params| Ok(XRule::new(parse_pairs(params)?)),
+            new: Some(|| XRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "ThermometerRule",
+            parse: |params| {
+                let mut themometer_clue = vec![];
+                let mut slow_themometer_clue = vec![];
+                for param in params {
+                    match param.strip_prefix("slow") {
+                        Some(ther) => slow_themometer_clue.push(parse_list(ther)?),
+                        None => themometer_clue.push(parse_list(param)?),
+                    }
+                }
+                Ok(ThermometerRule::new(themometer_clue, slow_themometer_clue))
+            },
+            new: Some(|| ThermometerRule::new(vec![], vec![])),
+        },
+        RuleEntry {
+            name: "BetweenLineRule",
+            parse: |params| {
+                let between_clue = params
+                    .iter()
+                    .map(|line| parse_list(line))
+                    .collect::<Result<Vec<Vec<usize>>, _>>()?;
+                if let Some(line) = between_clue
+                    .iter()
+                    .find(|line| line.len() < BetweenLineRule::MIN_LINE_LEN)
+                {
+                    return Err(format!(
+                        "A between line needs at least {} cells, but {line:?} has {}",
+                        BetweenLineRule::MIN_LINE_LEN,
+                        line.len()
+                    ));
+                }
+                Ok(BetweenLineRule::new(between_clue))
+            },
+            new: Some(|| BetweenLineRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "RegionSumLineRule",
+            parse: |params| {
+                Ok(RegionSumLineRule::new(
+                    params
+                        .iter()
+                        .map(|line| parse_list(line))
+                        .collect::<Result<_, _>>()?,
+                ))
+            },
+            new: Some(|| RegionSumLineRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "QuadrupleRule",
+            parse: |params| {
+                Ok(QuadrupleRule::new(
+                    params
+                        .iter()
+                        .map(|quad| {
+                            let Some((corner, digits)) = quad.split_once(',') else {
+                                return Err(format!("Failed to split {quad} on ,"));
+                            };
+                            let corner = corner.trim().parse().map_err(|e| format!("{e:?}"))?;
+                            Ok((corner, parse_list(digits)?))
+                        })
+                        .collect::<Result<_, _>>()?,
+                ))
+            },
+            new: Some(|| QuadrupleRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "ConstraintRule",
+            parse: |params| {
+                Ok(ConstraintRule::new(
+                    params
+                        .iter()
+                        .map(|param| parse_constraint(param))
+                        .collect::<Result<_, _>>()?,
+                ))
+            },
+            new: Some(|| ConstraintRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "LayoutRule",
+            parse: |params| {
+                let Some((size, params)) = params.split_first() else {
+                    return Err("LayoutRule needs a size".to_owned());
+                };
+                let size = size.parse().map_err(|e| format!("{e:?}"))?;
+                let mut grids = vec![];
+                for param in params {
+                    match *param {
+                        "samurai" => grids.extend(Layout::samurai(size).grids),
+                        "twodoku" => grids.extend(Layout::twodoku(size).grids),
+                        "butterfly" => grids.extend(Layout::butterfly(size).grids),
+                        corner => {
+                            let Some((x, y)) = corner.split_once(',') else {
+                                return Err(format!("Failed to split {corner} on ,"));
+                            };
+                            grids.push((
+                                x.trim().parse().map_err(|e| format!("{e:?}"))?,
+                                y.trim().parse().map_err(|e| format!("{e:?}"))?,
+                            ));
+                        }
+                    }
+                }
+                Ok(LayoutRule::new(Layout::new(size, grids)))
+            },
+            new: None,
+        },
+        RuleEntry {
+            name: "ExtraRegionsRule",
+            parse: |params| {
+                let mut extra_regions_clue = vec![];
+                let mut presets = vec![];
+                for param in params {
+                    match *param {
+                        "windoku" => presets.push(RegionPreset::Windoku),
+                        "disjoint" => presets.push(RegionPreset::DisjointGroups),
+                        region => extra_regions_clue.push(parse_list(region)?),
+                    }
+                }
+                Ok(ExtraRegionsRule::new(extra_regions_clue, presets))
+            },
+            new: Some(ExtraRegionsRule::windoku),
+        },
+        RuleEntry {
+            name: "SkyscraperRule",
+            parse: |params| {
+                let mut sides: [Vec<u16>; 4] = Default::default();
+                for param in params {
+                    let Some((side, clues)) = param.split_once(' ') else {
+                        return Err(format!("Failed to split {param} on space"));
+                    };
+                    let side = match side {
+                        "top" => 0,
+                        "bottom" => 1,
+                        "left" => 2,
+                        "right" => 3,
+                        other => return Err(format!("Unknown side {other}")),
+                    };
+                    sides[side] = parse_list(clues)?;
+                }
+                let [top, bottom, left, right] = sides;
+                Ok(SkyscraperRule::new(top, bottom, left, right))
+            },
+            new: Some(|| SkyscraperRule::new(vec![], vec![], vec![], vec![])),
+        },
+        RuleEntry {
+            name: "InequalityRule",
+            parse: |params| Ok(InequalityRule::new(parse_pairs(params)?)),
+            new: Some(|| InequalityRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "ZipperRule",
+            parse: |params| {
+                Ok(ZipperRule::new(
+                    params
+                        .iter()
+                        .map(|s| {
+                            let Some((center, rest)) = s.split_once(',') else {
+                                return Err(format!("Failed to split {s} on ,"));
+                            };
+
+                            let center = center.parse().map_err(|e| format!("{e:?}"))?;
+                            let indecies = rest.split(',').map(str::trim);
+
+                            let rest_resolved = indecies
+                                .map(|s| {
+                                    let Some((l, r)) = s.split_once('+') else {
+                                        return Err(format!("Failed to split {s} on +"));
+                                    };
+                                    let l = l.parse().map_err(|e| format!("{e:?}"))?;
+                                    let r = r.parse().map_err(|e| format!("{e:?}"))?;
+
+                                    Ok((l, r))
+                                })
+                                .collect::<Result<_, _>>()?;
+
+                            Ok((center, rest_resolved))
+                        })
+                        .collect::<Result<_, _>>()?,
+                ))
+            },
+            new: Some(|| ZipperRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "ParityRule",
+            parse: |params| Ok(ParityRule::new(parse_pairs(params)?)),
+            new: Some(|| ParityRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "ConsecutiveRule",
+            parse: |params| Ok(ConsecutiveRule::new(parse_pairs(params)?)),
+            new: Some(|| ConsecutiveRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "EvenOddRule",
+            parse: |params| {
+                Ok(EvenOddRule::new(
+                    params
+                        .iter()
+                        .map(|s| {
+                            let Some((index, parity)) = s.split_once(',') else {
+                                return Err(format!("Failed to split {s} on ,"));
+                            };
+                            let index = index.parse().map_err(|e| format!("{e:?}"))?;
+                            let is_even = match parity.trim() {
+                                "E" => true,
+                                "O" => false,
+                                other => return Err(format!("Expected E or O, found {other}")),
+                            };
+
+                            Ok((index, is_even))
+                        })
+                        .collect::<Result<_, _>>()?,
+                ))
+            },
+            new: Some(|| EvenOddRule::new(vec![])),
+        },
+        RuleEntry {
+            name: "SumDotRule",
+            parse: |params| {
+                let (sum_clue, sums, negative) = parse_dots(params, "sums")?;
+                // With no sums and no dots it is XV
+                if sums.is_empty() {
+                    return Ok(SumDotRule::xv(sum_clue, negative));
+                }
+                Ok(SumDotRule::new(sum_clue, sums, negative))
+            },
+            new: Some(|| SumDotRule::xv(vec![], false)),
+        },
+        RuleEntry {
+            name: "DifferenceDotRule",
+            parse: |params| {
+                let (difference_clue, differences, negative) = parse_dots(params, "differences")?;
+                // With no differences and no dots it is the white kropki dots
+                if differences.is_empty() {
+                    return Ok(DifferenceDotRule::consecutive(difference_clue, negative));
+                }
+                Ok(DifferenceDotRule::new(
+                    difference_clue,
+                    differences,
+                    negative,
+                ))
+            },
+            new: Some(|| DifferenceDotRule::consecutive(vec![], false)),
+        },
+    ]
+}
+
+// Parses a list of values separated by ,
+fn parse_list<T: FromStr>(list: &str) -> Result<Vec<T>, String>
+where
+    T::Err: Debug,
+{
+    list.split(',')
+        .map(|value| value.trim().parse().map_err(|e| format!("{e:?}")))
+        .collect()
+}
+
+// Parses the parameters of a rule where every clue is a pair of indexes "left,right", like the XRule
+fn parse_pairs(params: &[&str]) -> Result<Vec<(usize, usize)>, String> {
+    params
+        .iter()
+        .map(|s| {
+            let Some((l, r)) = s.split_once(',') else {
+                return Err(format!("Failed to split {s} on ,"));
+            };
+            let l = l.parse().map_err(|e| format!("{e:?}"))?;
+            let r = r.parse().map_err(|e| format!("{e:?}"))?;
+
+            Ok((l, r))
+        })
+        .collect()
+}
+
+// Parses a constraint given as "<predicate> <value> : a,b,c", where only some predicates have a value
+fn parse_constraint(param: &str) -> Result<(Predicate, Vec<usize>), String> {
+    let Some((predicate, group)) = param.split_once(':') else {
+        return Err(format!("Failed to split {param} on :"));
+    };
+    let mut words = predicate.split_whitespace();
+    let name = words.next();
+    let value = words.next().map(str::parse::<u64>);
+
+    let predicate = match (name, value) {
+        (Some("sum"), Some(Ok(sum))) => {
+            Predicate::Sum(sum.try_into().map_err(|e| format!("{e:?}"))?)
+        }
+        (Some("product"), Some(Ok(product))) => Predicate::Product(product),
+        (Some("difference"), Some(Ok(difference))) => {
+            Predicate::Difference(difference.try_into().map_err(|e| format!("{e:?}"))?)
+        }
+        (Some("distinct"), None) => Predicate::Distinct,
+        (Some("increasing"), None) => Predicate::Increasing,
+        (_, Some(Err(e))) => return Err(format!("{e:?}")),
+        _ => return Err(format!("Unknown predicate {predicate}")),
+    };
+
+    let group = group
+        .split(',')
+        .map(|index| index.trim().parse().map_err(|e| format!("{e:?}")))
+        .collect::<Result<_, _>>()?;
+    Ok((predicate, group))
+}
+
+// The dots as (left, right, value), the values the dots can have, and whether the rule is negative
+type DotClues = (Vec<(usize, usize, u16)>, Vec<u16>, bool);
+
+// Parses the parameters of a dot rule like the SumDotRule, where every dot is "left,right,value".
+// The list of values is given as "<values_name> a,b,c" and the negative mode as "negative".
+// Without a list of values, the values on the dots are used.
+fn parse_dots(params: &[&str], values_name: &str) -> Result<DotClues, String> {
+    let mut dots = vec![];
+    let mut values = vec![];
+    let mut negative = false;
+
+    for param in params {
+        if *param == "negative" {
+            negative = true;
+        } else if let Some(value_list) = param.strip_prefix(values_name) {
+            values = value_list
+                .split(',')
+                .map(|value| value.trim().parse().map_err(|e| format!("{e:?}")))
+                .collect::<Result<_, _>>()?;
+        } else {
+            let [left, right, value] = param.split(',').collect::<Vec<_>>()[..] else {
+                return Err(format!("Expected left,right,value but found {param}"));
+            };
+            dots.push((
+                left.trim().parse().map_err(|e| format!("{e:?}"))?,
+                right.trim().parse().map_err(|e| format!("{e:?}"))?,
+                value.trim().parse().map_err(|e| format!("{e:?}"))?,
+            ));
+        }
+    }
+
+    if values.is_empty() {
+        values = dots.iter().map(|(_, _, value)| *value).collect();
+        values.sort();
+        values.dedup();
+    }
+
+    Ok((dots, values, negative))
+}
+
+//########################### TEST ###############################
+
+// A rule defined outside the registry, where the top left cell is the highest digit
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct TestCornerRule;
+
+#[cfg(test)]
+impl super::Rule for TestCornerRule {
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &crate::sudoku::Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        for value in &sudoku.cells[0].available {
+            if *value != sudoku.size as u16 {
+                big_buffer.push((*value, 0));
+            }
+        }
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &crate::sudoku::Sudoku) -> bool {
+        sudoku.cells[0].available[0] == sudoku.size as u16
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule::new(self.clone())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "TestCornerRule"
+    }
+}
+
+#[test]
+fn registry_custom_rule_test() {
+    assert!("TestCornerRule".parse::<DynRule>().is_err());
+
+    register_rule(RuleEntry {
+        name: "TestCornerRule",
+        parse: |_| Ok(DynRule::new(TestCornerRule)),
+        new: Some(|| DynRule::new(TestCornerRule)),
+    });
+    assert!(rule_names().contains(&"TestCornerRule"));
+
+    let mut sudoku: crate::sudoku::Sudoku = "SquareRule | TestCornerRule\n\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0"
+        .parse()
+        .unwrap();
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.cells[0].is_single_eq(4));
+}
+
+#[test]
+fn registry_builtin_test() {
+    let registry = RuleRegistry::builtin();
+    assert!(registry.names().any(|name| name == "KnightsMove"));
+    assert!(registry.parse("Unknown ; 1,2").is_err());
+
+    let rule = registry.parse("XRule ; 0,1 ; 4,5").unwrap();
+    assert_eq!(rule.get_name(), "XRule");
+    assert_eq!(rule.no_of_clues(), 2);
+
+    assert_eq!(new_rule("XRule").unwrap().no_of_clues(), 0);
+    assert!(new_rule("LayoutRule").is_none());
+
+    assert_eq!(generation_rule("XRule").unwrap().no_of_clues(), 0);
+    let layout = generation_rule(" LayoutRule ; 4 ; twodoku ").unwrap();
+    assert!(layout.layout().is_some());
+    assert!(generation_rule("Unknown").is_err());
+}
@@ -126,6 +126,12 @@ impl Rule for RowRule {
         "RowRule"
     }
 
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::RowRule
+    }
+
     fn priority(&self) -> super::ExecutionPriority {
         super::ExecutionPriority::High
     }
@@ -164,6 +170,25 @@ fn locked_row_candidate() {
     assert_eq!(res, Some((2, vec![63, 64, 65, 66, 67, 68].as_slice())))
 }
 
+#[test]
+fn locked_row_candidate_fixture() {
+    // the 1 of the top left box is in the top row, so it is removed from the rest of that row
+    let sudoku = Sudoku::from_candidates(
+        "12   12   | 12   34
+         34   34   | 1234 1234
+         ----------+----------
+         1234 1234 | 1234 1234
+         1234 1234 | 1234 1234",
+        vec![super::square_rule::SquareRule::new()],
+    )
+    .unwrap();
+    let mut buffer = vec![];
+    let mut arena = Bump::new();
+
+    let res = RowRule::new().locked_candidate(&sudoku, &mut buffer, &mut arena);
+    assert_eq!(res, Some((1, vec![2].as_slice())));
+}
+
 #[test]
 fn row_hidden_math_test() {
     let mut sudoku = Sudoku::new(9, vec![super::square_rule::SquareRule::new()]);
@@ -0,0 +1,209 @@
+// This file is the serde representation of the rules.
+// Every rule is tagged with its name in the PSF and holds its clues, so it can be stored as JSON.
+
+use super::{
+    between_line_rule::BetweenLineRule,
+    column_rule::ColumnRule,
+    consecutive_rule::ConsecutiveRule,
+    constraint_rule::{ConstraintRule, Predicate},
+    diagonal_rule::DiagonalRule,
+    difference_dot_rule::DifferenceDotRule,
+    even_odd_rule::EvenOddRule,
+    extra_regions_rule::{ExtraRegionsRule, RegionPreset},
+    inequality_rule::InequalityRule,
+    knight_rule::KnightRule,
+    layout_rule::{Layout, LayoutRule},
+    non_consecutive_rule::NonConsecutiveRule,
+    parity_rule::ParityRule,
+    quadruple_rule::QuadrupleRule,
+    region_sum_line_rule::RegionSumLineRule,
+    registry,
+    row_rule::RowRule,
+    skyscraper_rule::SkyscraperRule,
+    square_rule::SquareRule,
+    sum_dot_rule::SumDotRule,
+    thermometer_rule::ThermometerRule,
+    x_rule::XRule,
+    zipper_rule::ZipperRule,
+    DynRule,
+};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule")]
+pub enum RuleData {
+    RowRule,
+    ColumnRule,
+    SquareRule,
+    #[serde(rename = "KnightsMove")]
+    KnightRule,
+    DiagonalRule,
+    NonConsecutiveRule,
+    XRule {
+        x_clue: Vec<(usize, usize)>,
+    },
+    ConsecutiveRule {
+        consecutive_clue: Vec<(usize, usize)>,
+    },
+    ParityRule {
+        parity_clue: Vec<(usize, usize)>,
+    },
+    InequalityRule {
+        inequality_clue: Vec<(usize, usize)>,
+    },
+    ZipperRule {
+        zipper_clue: Vec<(usize, Vec<(usize, usize)>)>,
+    },
+    ThermometerRule {
+        themometer_clue: Vec<Vec<usize>>,
+        slow_themometer_clue: Vec<Vec<usize>>,
+    },
+    BetweenLineRule {
+        between_clue: Vec<Vec<usize>>,
+    },
+    RegionSumLineRule {
+        region_sum_clue: Vec<Vec<usize>>,
+    },
+    EvenOddRule {
+        even_odd_clue: Vec<(usize, bool)>,
+    },
+    QuadrupleRule {
+        quadruple_clue: Vec<(usize, Vec<u16>)>,
+    },
+    SumDotRule {
+        sum_clue: Vec<(usize, usize, u16)>,
+        sums: Vec<u16>,
+        negative: bool,
+    },
+    DifferenceDotRule {
+        difference_clue: Vec<(usize, usize, u16)>,
+        differences: Vec<u16>,
+        negative: bool,
+    },
+    SkyscraperRule {
+        top_clue: Vec<u16>,
+        bottom_clue: Vec<u16>,
+        left_clue: Vec<u16>,
+        right_clue: Vec<u16>,
+    },
+    ExtraRegionsRule {
+        extra_regions_clue: Vec<Vec<usize>>,
+        presets: Vec<RegionPreset>,
+    },
+    ConstraintRule {
+        constraint_clue: Vec<(Predicate, Vec<usize>)>,
+    },
+    LayoutRule {
+        layout: Layout,
+    },
+    // A rule added to the registry from outside the crate, stored as it is written in the PSF
+    Custom {
+        psf: String,
+    },
+}
+
+impl RuleData {
+    pub fn into_rule(self) -> Result<DynRule, String> {
+        Ok(match self {
+            RuleData::RowRule => RowRule::new(),
+            RuleData::ColumnRule => ColumnRule::new(),
+            RuleData::SquareRule => SquareRule::new(),
+            RuleData::KnightRule => KnightRule::new(),
+            RuleData::DiagonalRule => DiagonalRule::new(),
+            RuleData::NonConsecutiveRule => NonConsecutiveRule::new(),
+            RuleData::XRule { x_clue } => XRule::new(x_clue),
+            RuleData::ConsecutiveRule { consecutive_clue } => {
+                ConsecutiveRule::new(consecutive_clue)
+            }
+            RuleData::ParityRule { parity_clue } => ParityRule::new(parity_clue),
+            RuleData::InequalityRule { inequality_clue } => InequalityRule::new(inequality_clue),
+            RuleData::ZipperRule { zipper_clue } => ZipperRule::new(zipper_clue),
+            RuleData::ThermometerRule {
+                themometer_clue,
+                slow_themometer_clue,
+            } => ThermometerRule::new(themometer_clue, slow_themometer_clue),
+            RuleData::BetweenLineRule { between_clue } => BetweenLineRule::new(between_clue),
+            RuleData::RegionSumLineRule { region_sum_clue } => {
+                RegionSumLineRule::new(region_sum_clue)
+            }
+            RuleData::EvenOddRule { even_odd_clue } => EvenOddRule::new(even_odd_clue),
+            RuleData::QuadrupleRule { quadruple_clue } => QuadrupleRule::new(quadruple_clue),
+            RuleData::SumDotRule {
+                sum_clue,
+                sums,
+                negative,
+            } => SumDotRule::new(sum_clue, sums, negative),
+            RuleData::DifferenceDotRule {
+                difference_clue,
+                differences,
+                negative,
+            } => DifferenceDotRule::new(difference_clue, differences, negative),
+            RuleData::SkyscraperRule {
+                top_clue,
+                bottom_clue,
+                left_clue,
+                right_clue,
+            } => SkyscraperRule::new(top_clue, bottom_clue, left_clue, right_clue),
+            RuleData::ExtraRegionsRule {
+                extra_regions_clue,
+                presets,
+            } => ExtraRegionsRule::new(extra_regions_clue, presets),
+            RuleData::ConstraintRule { constraint_clue } => ConstraintRule::new(constraint_clue),
+            RuleData::LayoutRule { layout } => LayoutRule::new(layout),
+            RuleData::Custom { psf } => registry::parse_rule(&psf)?,
+        })
+    }
+}
+
+impl Serialize for DynRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DynRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RuleData::deserialize(deserializer)?
+            .into_rule()
+            .map_err(D::Error::custom)
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn rule_data_round_trip_test() {
+    let psfs = [
+        "SquareRule",
+        "KnightsMove",
+        "XRule ;0,1 ;4,8",
+        "ZipperRule ;5,4+6,0+10",
+        "ThermometerRule ;0,1,2 ; slow 4,9,14",
+        "SumDotRule ; sums 5,10 ; negative ; 0,1,5",
+        "SkyscraperRule ; top 1,0,2,0 ; left 0,0,3,0",
+        "ExtraRegionsRule ; windoku ;0,1,2,3",
+        "ConstraintRule ; sum 10 : 0,1,2 ; distinct : 5,6,7",
+        "LayoutRule ; 4 ; 0,0 ; 2,2",
+    ];
+    for psf in psfs {
+        let rule: DynRule = psf.parse().unwrap();
+        let json = serde_json::to_string(&rule).unwrap();
+        let parsed: DynRule = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_psf(), rule.to_psf(), "{json}");
+    }
+}
+
+#[test]
+fn rule_data_json_test() {
+    let rule = XRule::new(vec![(0, 1)]);
+    assert_eq!(
+        serde_json::to_string(&rule).unwrap(),
+        r#"{"rule":"XRule","x_clue":[[0,1]]}"#
+    );
+
+    let rule: DynRule = serde_json::from_str(r#"{"rule":"KnightsMove"}"#).unwrap();
+    assert_eq!(rule.get_name(), "KnightRule");
+
+    assert!(serde_json::from_str::<DynRule>(r#"{"rule":"XRule"}"#).is_err());
+    assert!(serde_json::from_str::<DynRule>(r#"{"rule":"Custom","psf":"NoRule"}"#).is_err());
+}
@@ -0,0 +1,435 @@
+// This file is all the logic and tests pertaining to the Skyscraper rule
+
+use super::{
+    clue::{Clue, Side},
+    psf_list, DynRule, Rule,
+};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+// The search through all placements of a line is only done,
+// when the number of combinations of candidates is below this limit
+const SEARCH_LIMIT: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct SkyscraperRule {
+    // The clues outside each edge, from the top left corner along the edge. 0 means no clue.
+    // A clue is the number of buildings seen from that side, where the digits are the heights
+    // and a building hides all smaller buildings behind it.
+    pub top_clue: Vec<u16>,
+    pub bottom_clue: Vec<u16>,
+    pub left_clue: Vec<u16>,
+    pub right_clue: Vec<u16>,
+}
+
+impl SkyscraperRule {
+    pub fn new(
+        top_clue: Vec<u16>,
+        bottom_clue: Vec<u16>,
+        left_clue: Vec<u16>,
+        right_clue: Vec<u16>,
+    ) -> DynRule {
+        DynRule(Box::new(SkyscraperRule {
+            top_clue,
+            bottom_clue,
+            left_clue,
+            right_clue,
+        }))
+    }
+
+    fn sides(&self) -> [&Vec<u16>; 4] {
+        [
+            &self.top_clue,
+            &self.bottom_clue,
+            &self.left_clue,
+            &self.right_clue,
+        ]
+    }
+
+    // Returns every clue with the indexes of its row or column, in the order they are seen from the clue
+    fn lines(&self, size: usize) -> impl Iterator<Item = (u16, Vec<usize>)> + '_ {
+        self.sides()
+            .into_iter()
+            .enumerate()
+            .flat_map(move |(side, clues)| {
+                clues
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, clue)| **clue != 0)
+                    .map(move |(position, clue)| (*clue, line(size, side, position)))
+            })
+    }
+}
+
+// Returns the indexes of the row or column at the position along the side, in the order they are seen from the side.
+// The sides are top, bottom, left and right.
+fn line(size: usize, side: usize, position: usize) -> Vec<usize> {
+    match side {
+        0 => (0..size).map(|y| y * size + position).collect(),
+        1 => (0..size).rev().map(|y| y * size + position).collect(),
+        2 => (0..size).map(|x| position * size + x).collect(),
+        _ => (0..size).rev().map(|x| position * size + x).collect(),
+    }
+}
+
+// Returns how many buildings are seen from the start of the digits
+fn visible(digits: impl Iterator<Item = u16>) -> u16 {
+    let mut max = 0;
+    let mut count = 0;
+    for digit in digits {
+        if digit > max {
+            max = digit;
+            count += 1;
+        }
+    }
+    count
+}
+
+// Tries every placement of different digits along the line that agrees with the candidates,
+// and marks the digits of each placement that sees the clue as possible.
+// Returns true if at least one placement sees the clue.
+fn search(
+    sudoku: &Sudoku,
+    line: &[usize],
+    clue: u16,
+    placed: &mut Vec<u16>,
+    max: u16,
+    count: u16,
+    possible: &mut [u32],
+) -> bool {
+    let size = sudoku.size as u16;
+    let position = placed.len();
+
+    // Only digits above the highest building can be seen, so there is a limit to how many more can be seen
+    let unused_above = (max + 1..=size)
+        .filter(|digit| !placed.contains(digit))
+        .count() as u16;
+    let left = (line.len() - position) as u16;
+    if count > clue || count + unused_above.min(left) < clue {
+        return false;
+    }
+
+    if position == line.len() {
+        for (digit, mask) in placed.iter().zip(possible.iter_mut()) {
+            *mask |= 1 << digit;
+        }
+        return true;
+    }
+
+    let mut found = false;
+    for digit in &sudoku.cells[line[position]].available {
+        if placed.contains(digit) {
+            continue;
+        }
+        placed.push(*digit);
+        found |= if *digit > max {
+            search(sudoku, line, clue, placed, *digit, count + 1, possible)
+        } else {
+            search(sudoku, line, clue, placed, max, count, possible)
+        };
+        placed.pop();
+    }
+    found
+}
+
+impl Rule for SkyscraperRule {
+    // The clues are outside the grid, so placing a digit does not affect other cells
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // Sides without clues can be left out of the PSF, so they are filled with 0 when the size is known
+    fn set_size(&mut self, size: usize) {
+        for clue in [
+            &mut self.top_clue,
+            &mut self.bottom_clue,
+            &mut self.left_clue,
+            &mut self.right_clue,
+        ] {
+            clue.resize(size, 0);
+        }
+    }
+
+    // When a line has few enough combinations of candidates, every placement is tried,
+    // and the candidates that are not in any placement seeing the clue are removed.
+    // Otherwise the digit d cells from the clue can be at most size - clue + 1 + d,
+    // since the clue - 1 buildings before the tallest one need to be smaller and rising.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        let size = sudoku.size;
+        let mut placed = vec![];
+        let mut possible = vec![0; size];
+
+        for (clue, line) in self.lines(size) {
+            let combinations = line.iter().try_fold(1usize, |product, index| {
+                product
+                    .checked_mul(sudoku.cells[*index].available.len())
+                    .filter(|product| *product <= SEARCH_LIMIT)
+            });
+
+            if combinations.is_some() {
+                placed.clear();
+                possible.fill(0);
+                search(sudoku, &line, clue, &mut placed, 0, 0, &mut possible);
+
+                for (index, mask) in line.iter().zip(&possible) {
+                    for value in &sudoku.cells[*index].available {
+                        if mask & (1 << value) == 0 {
+                            big_buffer.push((*value, *index));
+                        }
+                    }
+                }
+            } else {
+                for (distance, index) in line.iter().enumerate() {
+                    let highest = (size + distance + 1).saturating_sub(clue as usize);
+                    for value in &sudoku.cells[*index].available {
+                        if *value as usize > highest {
+                            big_buffer.push((*value, *index));
+                        }
+                    }
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        self.lines(sudoku.size).all(|(clue, line)| {
+            visible(line.iter().map(|index| sudoku.cells[*index].available[0])) == clue
+        })
+    }
+
+    // Counts the buildings seen from every side of the solved sudoku,
+    // and removes some of the clues again
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, _width: usize) {
+        for (side, clues) in [
+            &mut self.top_clue,
+            &mut self.bottom_clue,
+            &mut self.left_clue,
+            &mut self.right_clue,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            *clues = (0..size)
+                .map(|position| {
+                    visible(
+                        line(size, side, position)
+                            .iter()
+                            .map(|index| cells[*index].available[0]),
+                    )
+                })
+                .collect();
+        }
+
+        for _ in 0..size {
+            let clue = random::<usize>() % size;
+            match random::<usize>() % 4 {
+                0 => self.top_clue[clue] = 0,
+                1 => self.bottom_clue[clue] = 0,
+                2 => self.left_clue[clue] = 0,
+                _ => self.right_clue[clue] = 0,
+            }
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        [
+            (Side::Top, &self.top_clue),
+            (Side::Bottom, &self.bottom_clue),
+            (Side::Left, &self.left_clue),
+            (Side::Right, &self.right_clue),
+        ]
+        .into_iter()
+        .flat_map(|(side, clues)| {
+            clues
+                .iter()
+                .enumerate()
+                .filter(|(_, clue)| **clue != 0)
+                .map(move |(position, clue)| Clue::Outside {
+                    side,
+                    position,
+                    value: *clue,
+                })
+        })
+        .collect()
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "SkyscraperRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.sides()
+            .iter()
+            .flat_map(|clues| clues.iter())
+            .filter(|clue| **clue != 0)
+            .count()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "SkyscraperRule".to_owned();
+        for (side, clues) in [
+            ("top", &self.top_clue),
+            ("bottom", &self.bottom_clue),
+            ("left", &self.left_clue),
+            ("right", &self.right_clue),
+        ] {
+            // A side without clues is left out, as it is filled with 0 when parsed
+            if !clues.is_empty() {
+                psf += &format!(" ; {side} {}", psf_list(clues));
+            }
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::SkyscraperRule {
+            top_clue: self.top_clue.clone(),
+            bottom_clue: self.bottom_clue.clone(),
+            left_clue: self.left_clue.clone(),
+            right_clue: self.right_clue.clone(),
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn skyscraper_search_test() {
+    let skyscraper_rule = SkyscraperRule {
+        top_clue: vec![0; 4],
+        bottom_clue: vec![0; 4],
+        left_clue: vec![4, 0, 0, 0],
+        right_clue: vec![0; 4],
+    };
+    let sudoku = Sudoku::new(4, vec![skyscraper_rule.boxed_clone()]);
+    let mut big_buffer = vec![];
+
+    // seeing all 4 buildings is only possible with the digits in rising order
+    let res = skyscraper_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        res,
+        vec![
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (1, 1),
+            (3, 1),
+            (4, 1),
+            (1, 2),
+            (2, 2),
+            (4, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3)
+        ]
+        .as_slice()
+    );
+}
+
+#[test]
+fn skyscraper_bound_test() {
+    let skyscraper_rule = SkyscraperRule {
+        top_clue: vec![0; 9],
+        bottom_clue: vec![0; 9],
+        left_clue: vec![3, 0, 0, 0, 0, 0, 0, 0, 0],
+        right_clue: vec![0; 9],
+    };
+    let sudoku = Sudoku::new(9, vec![skyscraper_rule.boxed_clone()]);
+    let mut big_buffer = vec![];
+
+    // there are too many combinations to search an empty row of a 9x9,
+    // but two buildings must be shorter than the 9 and before it
+    let res = skyscraper_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(8, 0), (9, 0), (9, 1)].as_slice());
+}
+
+#[test]
+fn skyscraper_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut skyscraper_rule = SkyscraperRule {
+        top_clue: vec![],
+        bottom_clue: vec![],
+        left_clue: vec![],
+        right_clue: vec![],
+    };
+
+    skyscraper_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    let expected = [
+        (&skyscraper_rule.top_clue, [3, 2, 2, 1]),
+        (&skyscraper_rule.bottom_clue, [1, 2, 2, 3]),
+        (&skyscraper_rule.left_clue, [4, 2, 2, 1]),
+        (&skyscraper_rule.right_clue, [1, 2, 2, 4]),
+    ];
+    for (clues, seen) in expected {
+        assert!(clues
+            .iter()
+            .zip(seen)
+            .all(|(clue, seen)| *clue == 0 || *clue == seen));
+    }
+    assert!(skyscraper_rule.no_of_clues() >= 12);
+    assert!(skyscraper_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn skyscraper_clues_test() {
+    let skyscraper_rule = SkyscraperRule {
+        top_clue: vec![0, 2, 0, 0],
+        bottom_clue: vec![0; 4],
+        left_clue: vec![],
+        right_clue: vec![0, 0, 0, 4],
+    };
+
+    assert_eq!(
+        skyscraper_rule.clues(),
+        vec![
+            Clue::Outside {
+                side: Side::Top,
+                position: 1,
+                value: 2
+            },
+            Clue::Outside {
+                side: Side::Right,
+                position: 3,
+                value: 4
+            }
+        ]
+    );
+}
+
+#[test]
+fn skyscraper_solve_test() {
+    let mut sudoku: Sudoku = "SkyscraperRule ; top 3,2,2,1 ; left 4,2,2,1\n\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0"
+        .parse()
+        .unwrap();
+
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&sudoku)));
+    assert_eq!(sudoku.cells[0].available[0], 1);
+    assert_eq!(sudoku.cells[3].available[0], 4);
+}
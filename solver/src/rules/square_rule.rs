@@ -183,9 +183,14 @@ impl Rule for SquareRule {
         None
     }
 
-    fn print_self(&self) -> bool {
-        print!("SquareRule");
-        true
+    fn to_psf(&self) -> Option<String> {
+        Some("SquareRule".to_owned())
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::SquareRule
     }
 }
 
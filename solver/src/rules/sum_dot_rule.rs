@@ -0,0 +1,278 @@
+// This file is all the logic and tests pertaining to the Sum dot rule
+
+use super::{
+    active_pairs, clue::Clue, dot_clue_limit, psf_list, push_unsupported, share_house, DynRule,
+    Rule,
+};
+use rand::random;
+use std::fmt::Debug;
+
+use crate::sudoku::{Cell, Sudoku};
+
+#[derive(Debug, Clone)]
+pub struct SumDotRule {
+    // touples of (left index, right index, sum), where the two cells must add to the sum
+    pub sum_clue: Vec<(usize, usize, u16)>,
+    // the sums the dots can have, like V = 5 and X = 10.
+    // These are the sums create_clue marks, and the sums the negative constraint forbids.
+    pub sums: Vec<u16>,
+    // if all dots are given, adjacent cells without a dot may not add to any of the sums
+    pub negative: bool,
+}
+
+impl SumDotRule {
+    pub fn new(sum_clue: Vec<(usize, usize, u16)>, sums: Vec<u16>, negative: bool) -> DynRule {
+        DynRule(Box::new(SumDotRule {
+            sum_clue,
+            sums,
+            negative,
+        }))
+    }
+
+    // The classic XV sudoku, where the sums are V = 5 and X = 10
+    pub fn xv(sum_clue: Vec<(usize, usize, u16)>, negative: bool) -> DynRule {
+        Self::new(sum_clue, vec![5, 10], negative)
+    }
+
+    fn has_dot(&self, left: usize, right: usize) -> bool {
+        self.sum_clue
+            .iter()
+            .any(|(l, r, _)| (*l == left && *r == right) || (*l == right && *r == left))
+    }
+}
+
+impl Rule for SumDotRule {
+    // Updates for domino rules do not affect other cells
+    fn updates<'buf>(
+        &self,
+        _size: usize,
+        _index: usize,
+        buffer: &'buf mut Vec<usize>,
+    ) -> &'buf [usize] {
+        buffer.clear();
+        buffer
+    }
+
+    // Removes every candidate on a dot, that can not add up to the sum with any candidate in the other half.
+    // In negative mode the same is done for the adjacent cells without a dot, where the cells may not add to any sum.
+    // Placed digits are checked as well, so the solver drops a broken branch right away.
+    fn multi_remove<'buf>(
+        &self,
+        sudoku: &Sudoku,
+        big_buffer: &'buf mut Vec<(u16, usize)>,
+    ) -> &'buf [(u16, usize)] {
+        big_buffer.clear();
+        let mut buffer = vec![];
+
+        for (left, right, sum) in &self.sum_clue {
+            let allowed = |a: u16, b: u16| a + b == *sum;
+            let left_cell = &sudoku.cells[*left];
+            let right_cell = &sudoku.cells[*right];
+
+            let distinct = share_house(sudoku, *left, *right, &mut buffer);
+
+            push_unsupported(left_cell, right_cell, *right, distinct, allowed, big_buffer);
+            push_unsupported(right_cell, left_cell, *left, distinct, allowed, big_buffer);
+        }
+
+        if self.negative {
+            let allowed = |a: u16, b: u16| !self.sums.contains(&(a + b));
+
+            for (left, right) in active_pairs(&sudoku.cells, sudoku.width()) {
+                if self.has_dot(left, right) {
+                    continue;
+                }
+                let left_cell = &sudoku.cells[left];
+                let right_cell = &sudoku.cells[right];
+                let distinct = share_house(sudoku, left, right, &mut buffer);
+
+                // A cell can only rule out a candidate next to it, if all its candidates are the candidate or one of the partners
+                if left_cell.available.len() <= self.sums.len() + 1 {
+                    push_unsupported(left_cell, right_cell, right, distinct, allowed, big_buffer);
+                }
+                if right_cell.available.len() <= self.sums.len() + 1 {
+                    push_unsupported(right_cell, left_cell, left, distinct, allowed, big_buffer);
+                }
+            }
+        }
+
+        big_buffer
+    }
+
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        let value = |index: usize| sudoku.cells[index].available[0];
+
+        self.sum_clue
+            .iter()
+            .all(|(left, right, sum)| value(*left) + value(*right) == *sum)
+            && (!self.negative
+                || active_pairs(&sudoku.cells, sudoku.width()).all(|(left, right)| {
+                    self.has_dot(left, right) || !self.sums.contains(&(value(left) + value(right)))
+                }))
+    }
+
+    // Marks all the adjacent cells in the solved sudoku that add to one of the sums
+    fn create_clue(&mut self, cells: &Vec<Cell>, size: usize, width: usize) {
+        for (left, right) in active_pairs(cells, width) {
+            let sum = cells[left].available[0] + cells[right].available[0];
+            if self.sums.contains(&sum) && !self.has_dot(left, right) {
+                self.sum_clue.push((left, right, sum));
+            }
+        }
+
+        // In negative mode every dot has to be given, otherwise remove some of them
+        let count = self.sum_clue.len();
+        let limit = dot_clue_limit(size);
+        if !self.negative && count > limit {
+            for i in 0..count - limit {
+                self.sum_clue.remove(random::<usize>() % (count - i));
+            }
+        }
+    }
+
+    fn clues(&self) -> Vec<Clue> {
+        self.sum_clue
+            .iter()
+            .map(|(left, right, value)| Clue::Domino {
+                cells: (*left, *right),
+                value: Some(*value),
+            })
+            .collect()
+    }
+
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
+    fn boxed_clone(&self) -> DynRule {
+        DynRule(Box::new(self.clone()))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "SumDotRule"
+    }
+
+    fn no_of_clues(&self) -> usize {
+        self.sum_clue.len()
+    }
+
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = format!("SumDotRule ; sums {}", psf_list(&self.sums));
+        if self.negative {
+            psf += " ; negative";
+        }
+        for (left, right, sum) in &self.sum_clue {
+            psf += &format!(" ; {left},{right},{sum}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::SumDotRule {
+            sum_clue: self.sum_clue.clone(),
+            sums: self.sums.clone(),
+            negative: self.negative,
+        }
+    }
+}
+
+//########################### TEST ###############################
+
+#[test]
+fn sum_dot_multi_remove_test() {
+    let sum_dot_rule = SumDotRule {
+        sum_clue: vec![(0, 1, 5), (4, 8, 7)],
+        sums: vec![5, 7],
+        negative: false,
+    };
+    let mut sudoku = Sudoku::new(
+        4,
+        vec![
+            super::square_rule::SquareRule::new(),
+            sum_dot_rule.boxed_clone(),
+        ],
+    );
+    let mut big_buffer = vec![];
+
+    // 7 can only be 3+4 on a 4x4, while 5 can be both 1+4 and 2+3
+    let res = sum_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(1, 8), (2, 8), (1, 4), (2, 4)].as_slice());
+
+    // With a 1 in the corner the 5 has to be 1+4, and the 1 is already gone from the cells of the 7
+    sudoku.set_cell(1, 0).unwrap();
+    let res = sum_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(res, vec![(2, 1), (3, 1), (2, 8), (2, 4)].as_slice());
+}
+
+#[test]
+fn sum_dot_negative_test() {
+    let sum_dot_rule = SumDotRule {
+        sum_clue: vec![(0, 1, 5)],
+        sums: vec![5],
+        negative: true,
+    };
+    let mut sudoku = Sudoku::new(4, vec![super::square_rule::SquareRule::new()]);
+    let mut big_buffer = vec![];
+
+    sudoku.set_cell(1, 5).unwrap();
+    let res = sum_dot_rule.multi_remove(&sudoku, &mut big_buffer);
+    // The 1 is removed from the V, and the 4 is removed around the 1 without a dot
+    assert_eq!(
+        res,
+        vec![(4, 1), (4, 0), (4, 1), (4, 4), (4, 6), (4, 9)].as_slice()
+    );
+}
+
+#[test]
+fn sum_dot_layout_test() {
+    // The cells 3 and 4 are in different grids, so the dot between them can be 2+2
+    let grid = vec!["0"; 32].join(",").replacen("0,0,0,0", "0,0,0,2", 1);
+    let psf = format!("LayoutRule ; 4 ; 0,0 ; 4,0 | SumDotRule ; 3,4,4\n\n{grid}");
+    let mut sudoku: Sudoku = psf.parse().unwrap();
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.cells[4].is_single_eq(2));
+
+    let mut sudoku: Sudoku = psf.replace("3,4,4", "3,4,5").parse().unwrap();
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.cells[4].is_single_eq(3));
+}
+
+#[test]
+fn sum_dot_create_clue_test() {
+    let sudoku: Sudoku = "1,2,3,4,\n3,4,1,2,\n2,1,4,3,\n4,3,2,1".parse().unwrap();
+    let mut sum_dot_rule = SumDotRule {
+        sum_clue: vec![],
+        sums: vec![5, 7],
+        negative: true,
+    };
+
+    sum_dot_rule.create_clue(&sudoku.cells, sudoku.size, sudoku.width());
+
+    assert_eq!(
+        sum_dot_rule.sum_clue,
+        vec![
+            (1, 2, 5),
+            (2, 3, 7),
+            (4, 5, 7),
+            (4, 8, 5),
+            (5, 6, 5),
+            (5, 9, 5),
+            (6, 10, 5),
+            (7, 11, 5),
+            (9, 10, 5),
+            (10, 11, 7),
+            (12, 13, 7),
+            (13, 14, 5)
+        ]
+    );
+    assert!(sum_dot_rule.finished_legal(&sudoku));
+}
+
+#[test]
+fn sum_dot_parse_test() {
+    let rule: DynRule = "SumDotRule ; negative ; 0,1,5 ; 1,2,10".parse().unwrap();
+    assert_eq!(rule.get_name(), "SumDotRule");
+    assert_eq!(rule.no_of_clues(), 2);
+}
@@ -3,7 +3,7 @@
 
 use rand::random;
 
-use super::{DynRule, Rule};
+use super::{box_of, clue::Clue, psf_list, DynRule, Rule};
 use std::fmt::Debug;
 
 use crate::sudoku::Sudoku;
@@ -12,11 +12,52 @@ use crate::sudoku::Sudoku;
 pub struct ThermometerRule {
     // Vector of themometers contaning indexes in rising order
     pub themometer_clue: Vec<Vec<usize>>,
+    // Vector of slow themometers contaning indexes in non-decreasing order.
+    // A digit can repeat on a slow themometer, when the two cells do not share a house.
+    pub slow_themometer_clue: Vec<Vec<usize>>,
 }
 
 impl ThermometerRule {
-    pub fn new(themometer_clue: Vec<Vec<usize>>) -> DynRule {
-        DynRule(Box::new(ThermometerRule { themometer_clue }))
+    pub fn new(themometer_clue: Vec<Vec<usize>>, slow_themometer_clue: Vec<Vec<usize>>) -> DynRule {
+        DynRule(Box::new(ThermometerRule {
+            themometer_clue,
+            slow_themometer_clue,
+        }))
+    }
+
+    // Returns the lowest and highest digit each cell on a slow thermometer can have.
+    // Each cell is at least the cell before it, and above it if the two cells share a row, column or box.
+    fn slow_bounds(sudoku: &Sudoku, themometer: &[usize]) -> Vec<(u16, u16)> {
+        let shares_house = |a: usize, b: usize| {
+            a / sudoku.size == b / sudoku.size
+                || a % sudoku.size == b % sudoku.size
+                || (sudoku.has_square
+                    && box_of(sudoku.size, sudoku.size, a) == box_of(sudoku.size, sudoku.size, b))
+        };
+
+        let mut bounds: Vec<(u16, u16)> = themometer
+            .iter()
+            .map(|index| {
+                let available = &sudoku.cells[*index].available;
+                (
+                    available.iter().min().copied().unwrap_or(0),
+                    available.iter().max().copied().unwrap_or(0),
+                )
+            })
+            .collect();
+
+        for position in 1..themometer.len() {
+            let step = shares_house(themometer[position - 1], themometer[position]) as u16;
+            bounds[position].0 = bounds[position].0.max(bounds[position - 1].0 + step);
+        }
+        for position in (1..themometer.len()).rev() {
+            let step = shares_house(themometer[position - 1], themometer[position]) as u16;
+            bounds[position - 1].1 = bounds[position - 1]
+                .1
+                .min(bounds[position].1.saturating_sub(step));
+        }
+
+        bounds
     }
 }
 
@@ -89,11 +130,11 @@ impl Rule for ThermometerRule {
                 {
                     let prev_index = themometer[enumeration - 1];
                     let next_index = themometer[enumeration + 1];
+                    // The next cell can be lower than the previous while guessing, so the difference is checked without subtracting
                     if sudoku.cells[next_index].locked_in
                         && sudoku.cells[prev_index].locked_in
                         && sudoku.cells[next_index].available[0]
-                            - sudoku.cells[prev_index].available[0]
-                            == 2
+                            == sudoku.cells[prev_index].available[0] + 2
                     {
                         return Some((
                             (sudoku.cells[themometer[enumeration - 1]].available[0] + 1),
@@ -103,6 +144,23 @@ impl Rule for ThermometerRule {
                 }
             }
         }
+
+        // If only one candidate of a cell on a slow thermometer is between the cells around it, it must be that candidate
+        for themometer in &self.slow_themometer_clue {
+            let bounds = Self::slow_bounds(sudoku, themometer);
+            for (index, (low, high)) in themometer.iter().zip(bounds) {
+                if sudoku.cells[*index].locked_in {
+                    continue;
+                }
+                let mut in_bounds = sudoku.cells[*index]
+                    .available
+                    .iter()
+                    .filter(|value| low <= **value && **value <= high);
+                if let (Some(value), None) = (in_bounds.next(), in_bounds.next()) {
+                    return Some((*value, *index));
+                }
+            }
+        }
         None
     }
 
@@ -177,11 +235,36 @@ impl Rule for ThermometerRule {
             }
         }
 
+        // Removes all values outside the bounds of each cell on the slow thermometers.
+        // Placed digits are checked as well, so the solver drops a broken branch right away.
+        for themometer in &self.slow_themometer_clue {
+            let bounds = Self::slow_bounds(sudoku, themometer);
+            for (index, (low, high)) in themometer.iter().zip(bounds) {
+                for value in &sudoku.cells[*index].available {
+                    if *value < low || *value > high {
+                        big_buffer.push((*value, *index));
+                    }
+                }
+            }
+        }
+
         big_buffer
     }
 
+    // Repeated digits in a house on a slow thermometer are caught by the rules of that house
+    fn finished_legal(&self, sudoku: &Sudoku) -> bool {
+        let value = |index: &usize| sudoku.cells[*index].available[0];
+        self.themometer_clue.iter().all(|ther| {
+            ther.windows(2)
+                .all(|pair| value(&pair[0]) < value(&pair[1]))
+        }) && self.slow_themometer_clue.iter().all(|ther| {
+            ther.windows(2)
+                .all(|pair| value(&pair[0]) <= value(&pair[1]))
+        })
+    }
+
     // The create clue function
-    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize) {
+    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize, _width: usize) {
         let tries = size * 3;
         let mut seen = vec![];
 
@@ -280,8 +363,17 @@ impl Rule for ThermometerRule {
         "ThemometerRule"
     }
 
-    fn to_thermometer_rule(&mut self) -> Option<&mut ThermometerRule> {
-        Some(self)
+    // The slow thermometers are labeled, so they can be drawn differently
+    fn clues(&self) -> Vec<Clue> {
+        let themometers = self.themometer_clue.iter().map(|ther| Clue::Line {
+            cells: ther.clone(),
+            label: None,
+        });
+        let slow_themometers = self.slow_themometer_clue.iter().map(|ther| Clue::Line {
+            cells: ther.clone(),
+            label: Some("slow"),
+        });
+        themometers.chain(slow_themometers).collect()
     }
 
     fn no_of_clues(&self) -> usize {
@@ -289,18 +381,24 @@ impl Rule for ThermometerRule {
     }
 
     // Prints the PSF part of the thermometer (PSF is explained in the report)
-    fn print_self(&self) -> bool {
-        print!("ThermometerRule");
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ThermometerRule".to_owned();
         for ther in &self.themometer_clue {
-            print!(" ;");
-            for (l_index, index) in ther.iter().enumerate() {
-                print!("{index}");
-                if l_index != ther.len() - 1 {
-                    print!(",");
-                }
-            }
+            psf += &format!(" ;{}", psf_list(ther));
+        }
+        for ther in &self.slow_themometer_clue {
+            psf += &format!(" ; slow {}", psf_list(ther));
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ThermometerRule {
+            themometer_clue: self.themometer_clue.clone(),
+            slow_themometer_clue: self.slow_themometer_clue.clone(),
         }
-        true
     }
 }
 
@@ -312,6 +410,7 @@ fn themometer_test() {
 
     let themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![0 as usize, 1 as usize, 2 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     let mut buffer = vec![];
@@ -321,7 +420,7 @@ fn themometer_test() {
 
     indexes = themometer_rule.updates(sudoku.size, 5, &mut buffer);
 
-    assert_eq!(indexes, vec![]);
+    assert_eq!(indexes, Vec::<usize>::new());
 }
 
 #[test]
@@ -330,6 +429,7 @@ fn themometer_multi_remove_test() {
 
     let mut themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![0 as usize, 1 as usize, 2 as usize, 3 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     let mut big_buffer = vec![];
@@ -372,6 +472,7 @@ fn themometer_multi_remove_test() {
     println!("");
     themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![3 as usize, 2 as usize, 1 as usize, 0 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     big_buffer.clear();
@@ -408,6 +509,7 @@ fn themometer_hidden_single_test() {
 
     let themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![0 as usize, 1 as usize, 2 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     sudoku.set_cell(1, 0).unwrap();
@@ -424,6 +526,7 @@ fn themometer_hidden_single_test() {
         themometer_clue: vec![vec![
             0 as usize, 1 as usize, 2 as usize, 3 as usize, 4 as usize,
         ]],
+        slow_themometer_clue: vec![],
     };
 
     sudoku.set_cell(1, 0).unwrap();
@@ -440,6 +543,7 @@ fn themometer_hidden_single_test() {
 
     let themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![0 as usize, 1 as usize, 2 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     sudoku.set_cell(2, 1).unwrap();
@@ -453,6 +557,7 @@ fn themometer_hidden_single_test() {
 
     let themometer_rule = ThermometerRule {
         themometer_clue: vec![vec![0 as usize, 1 as usize, 2 as usize]],
+        slow_themometer_clue: vec![],
     };
 
     sudoku.set_cell(1, 0).unwrap();
@@ -462,3 +567,64 @@ fn themometer_hidden_single_test() {
 
     assert_eq!(result, Some((9, 2)));
 }
+
+#[test]
+fn slow_themometer_multi_remove_test() {
+    let mut sudoku = Sudoku::new(9, vec![]);
+
+    // the last step is diagonal, so the two cells do not share a row or column without the SquareRule
+    let themometer_rule = ThermometerRule {
+        themometer_clue: vec![],
+        slow_themometer_clue: vec![vec![0, 1, 11]],
+    };
+
+    let mut big_buffer = vec![];
+    let indexes = themometer_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(indexes, vec![(9, 0), (1, 1), (1, 11)]);
+
+    // the 9 can repeat in the last cell
+    sudoku.set_cell(9, 1).unwrap();
+    let indexes = themometer_rule.multi_remove(&sudoku, &mut big_buffer);
+    assert_eq!(
+        indexes,
+        vec![
+            (1, 11),
+            (2, 11),
+            (3, 11),
+            (4, 11),
+            (5, 11),
+            (6, 11),
+            (7, 11),
+            (8, 11)
+        ]
+    );
+}
+
+#[test]
+fn slow_themometer_hidden_single_test() {
+    let mut sudoku = Sudoku::new(9, vec![]);
+
+    let themometer_rule = ThermometerRule {
+        themometer_clue: vec![],
+        slow_themometer_clue: vec![vec![0, 1, 11]],
+    };
+
+    assert_eq!(themometer_rule.hidden_singles(&sudoku), None);
+
+    sudoku.set_cell(9, 1).unwrap();
+    assert_eq!(themometer_rule.hidden_singles(&sudoku), Some((9, 11)));
+}
+
+#[test]
+fn slow_themometer_solve_test() {
+    let mut sudoku: Sudoku = "SquareRule | ThermometerRule ; 0,1,2,3 ; slow 4,9,14\n\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0"
+        .parse()
+        .unwrap();
+
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&sudoku)));
+}
@@ -1,7 +1,7 @@
 // This file is all the logic and tests pertaining to the X rule
 // Author Katinka s224805 and Kasper s224776
 
-use super::{DynRule, Rule};
+use super::{active_pairs, clue::Clue, DynRule, Rule};
 use bumpalo::Bump;
 use rand::random;
 use std::fmt::Debug;
@@ -110,32 +110,11 @@ impl Rule for XRule {
     }
 
     // The create clue function
-    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize) {
-
+    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize, width: usize) {
         // first find all the places where the x-rule is satisfied:
-        for index in 0..cells.len() {
-            if let Some(current) = cells[index].available.get(0) {
-                if index + 1 >= cells.len() {
-                    continue;
-                }
-                // check to the left 
-                if let Some(left) = cells[index + 1].available.get(0) {
-                    if current + left == size as u16 + 1 && (index + 1) % size != 0 {
-                        // x rule should have (index , left)
-                        self.x_clue.push((index, index + 1));
-                    }
-                }
-                // if the bottom of the sudoku has been reached, do not check out of bounds
-                if index + size >= cells.len() {
-                    continue;
-                }
-                // check below
-                if let Some(below) = cells[index + size].available.get(0) {
-                    if current + below == size as u16 + 1 && index + size < cells.len() {
-                        // x rule should have (index , below)
-                        self.x_clue.push((index, index + size));
-                    }
-                }
+        for (left, right) in active_pairs(cells, width) {
+            if cells[left].available[0] + cells[right].available[0] == size as u16 + 1 {
+                self.x_clue.push((left, right));
             }
         }
 
@@ -148,6 +127,10 @@ impl Rule for XRule {
         }
     }
 
+    fn supports_layout(&self) -> bool {
+        true
+    }
+
     fn boxed_clone(&self) -> DynRule {
         DynRule(Box::new(self.clone()))
     }
@@ -156,20 +139,34 @@ impl Rule for XRule {
         "XRule"
     }
 
-    fn to_x_rule(&mut self) -> Option<&mut XRule> {
-        Some(self)
+    fn clues(&self) -> Vec<Clue> {
+        self.x_clue
+            .iter()
+            .map(|(left, right)| Clue::Domino {
+                cells: (*left, *right),
+                value: None,
+            })
+            .collect()
     }
 
     fn no_of_clues(&self) -> usize {
         return self.x_clue.len();
     }
 
-    fn print_self(&self) -> bool {
-        print!("XRule");
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "XRule".to_owned();
         for (x, y) in &self.x_clue {
-            print!(" ;{x},{y}");
+            psf += &format!(" ;{x},{y}");
+        }
+        Some(psf)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::XRule {
+            x_clue: self.x_clue.clone(),
         }
-        true
     }
 }
 
@@ -1,7 +1,7 @@
 // This file is all the logic and tests pertaining to the Zipper rule
 // Author Katinka s224805
 
-use super::{DynRule, Rule};
+use super::{clue::Clue, DynRule, Rule};
 use integer_sqrt::IntegerSquareRoot;
 use rand::random;
 use std::fmt::Debug;
@@ -256,7 +256,7 @@ impl Rule for ZipperRule {
         big_buffer
     }
 
-    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize) {
+    fn create_clue(&mut self, cells: &Vec<crate::sudoku::Cell>, size: usize, _width: usize) {
         let tries = size * 3;
         let mut seen = vec![];
 
@@ -405,8 +405,21 @@ impl Rule for ZipperRule {
         "ZipperRule"
     }
 
-    fn to_zipper_rule(&mut self) -> Option<&mut ZipperRule> {
-        Some(self)
+    // The line goes from the end of one arm through the center to the end of the other arm
+    fn clues(&self) -> Vec<Clue> {
+        self.zipper_clue
+            .iter()
+            .map(|(center, arms)| Clue::Line {
+                cells: arms
+                    .iter()
+                    .rev()
+                    .map(|(left, _)| *left)
+                    .chain(std::iter::once(*center))
+                    .chain(arms.iter().map(|(_, right)| *right))
+                    .collect(),
+                label: None,
+            })
+            .collect()
     }
 
     fn finished_legal(&self, sudoku: &Sudoku) -> bool {
@@ -423,19 +436,24 @@ impl Rule for ZipperRule {
     }
     
     // Prints the zippers
-    fn print_self(&self) -> bool {
-        print!("ZipperRule");
+    fn to_psf(&self) -> Option<String> {
+        let mut psf = "ZipperRule".to_owned();
         for (center, pairlist) in &self.zipper_clue {
-            print!(" ;{center},");
+            let pairs: Vec<String> = pairlist
+                .iter()
+                .map(|(left, right)| format!("{left}+{right}"))
+                .collect();
+            psf += &format!(" ;{center},{}", pairs.join(","));
+        }
+        Some(psf)
+    }
 
-            for (index, (left, right)) in pairlist.iter().enumerate() {
-                print!("{left}+{right}");
-                if index != pairlist.len() - 1 {
-                    print!(",");
-                }
-            }
+    #[cfg(feature = "serde")]
+    fn to_data(&self) -> super::rule_data::RuleData {
+        use super::rule_data::RuleData;
+        RuleData::ZipperRule {
+            zipper_clue: self.zipper_clue.clone(),
         }
-        true
     }
 }
 
@@ -594,3 +612,18 @@ fn avalible_test() {
 
     println!("{sudoku}");
 }
+
+#[test]
+fn zipper_clues_test() {
+    let zipper_rule = ZipperRule {
+        zipper_clue: vec![(4, vec![(3, 5), (2, 6)])],
+    };
+
+    assert_eq!(
+        zipper_rule.clues(),
+        vec![Clue::Line {
+            cells: vec![2, 3, 4, 5, 6],
+            label: None
+        }]
+    );
+}
@@ -20,11 +20,17 @@ use regex_macro::regex;
 use smallvec::{smallvec, SmallVec};
 use threadpool::ThreadPool;
 
-use crate::rules::{
-    column_rule::ColumnRule, row_rule::RowRule, square_rule::SquareRule, DynRule, Rule,
+use crate::{
+    formats::alphabet::Alphabet,
+    metadata::Metadata,
+    rules::{
+        column_rule::ColumnRule, layout_rule::Layout, row_rule::RowRule, square_rule::SquareRule,
+        DynRule, Rule,
+    },
 };
 // Author Thor s224817
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Difficulty {
     Easy,
     Medium,
@@ -63,6 +69,9 @@ pub struct Sudoku {
     pub cells: Vec<Cell>,
     pub rules: SmallVec<[DynRule; 8]>,
     pub has_square: bool,
+    // Set when the cells are a canvas of several overlapping grids instead of a single grid
+    pub layout: Option<Layout>,
+    pub metadata: Metadata,
 }
 
 // Author Thor s224817
@@ -89,6 +98,11 @@ pub enum SudokuSolveError {
     AlreadyManySolutions,
     UnsolveableError,
     RemovedLockedValue,
+    BranchLimitReached,
+    // The rule looks at the rows, columns or boxes of a single grid, so it can not be used with a layout
+    UnsupportedByLayout(&'static str),
+    // The layout is made of grids of the size it holds, which is not the size of the sudoku
+    LayoutSizeMismatch(usize),
 }
 
 // Author Thor s224817
@@ -173,6 +187,9 @@ impl Display for SudokuSolveError {
             SudokuSolveError::UnsolveableError => write!(f, "No Solution. Failed to pop branch queue when entropy was 0 in cell"),
             SudokuSolveError::RemovedLockedValue => write!(f, "Something went seriously wrong. Removed the only value in a locked cell\nThis indicates a bug in the rules."),
             SudokuSolveError::AlreadyManySolutions => write!(f, "Has already found more than 1 solution when searching for all solutions. Short circuting"),
+            SudokuSolveError::BranchLimitReached => write!(f, "Gave up after guessing too many times"),
+            SudokuSolveError::UnsupportedByLayout(name) => write!(f, "{name} does not work with a layout"),
+            SudokuSolveError::LayoutSizeMismatch(layout_size) => write!(f, "The layout is made of grids of size {layout_size}"),
         }
     }
 }
@@ -186,33 +203,142 @@ impl Sudoku {
     // Author Thor s224817 and Katinka s224805
     //Create a new Sudoku with a size and list of rules
     pub fn new(size: usize, mut rules: Vec<DynRule>) -> Self {
-        if !rules.iter().any(|r| r.get_name() == "ColumnRule") {
-            rules.push(ColumnRule::new());
-        }
-        if !rules.iter().any(|r| r.get_name() == "RowRule") {
-            rules.push(RowRule::new());
+        // A layout brings the rows and columns of each of its grids, so the canvas has no rows or columns of its own
+        let layout = rules.iter().find_map(|r| r.layout()).cloned();
+        if let Some(layout) = &layout {
+            assert_eq!(
+                layout.size, size,
+                "The layout must have the size of the sudoku"
+            );
+        } else {
+            if !rules.iter().any(|r| r.get_name() == "ColumnRule") {
+                rules.push(ColumnRule::new());
+            }
+            if !rules.iter().any(|r| r.get_name() == "RowRule") {
+                rules.push(RowRule::new());
+            }
         }
 
+        for rule in &mut rules {
+            rule.set_size(size);
+        }
         rules.sort_by_key(|a| a.priority());
         let has_square: bool = rules
             .iter()
             .any(|rule| rule.get_name() == SquareRule.get_name());
 
-        Self {
-            size,
-            cells: (0..size * size)
+        // The cells outside the grids of a layout are locked to 0, so the solver never touches them
+        let cells = match &layout {
+            Some(layout) => (0..layout.width() * layout.height())
+                .map(|index| {
+                    if layout.is_active(index) {
+                        Cell::new_with_range(1..(size as u16 + 1))
+                    } else {
+                        Cell::single(0)
+                    }
+                })
+                .collect(),
+            None => (0..size * size)
                 .map(|_| Cell::new_with_range(1..(size as u16 + 1)))
                 .collect(),
+        };
+
+        Self {
+            size,
+            cells,
             rules: rules.into(),
             has_square,
+            layout,
+            metadata: Metadata::default(),
         }
     }
     // Author Thor s224817
     pub fn reset_locked(&mut self) {
-        for cell in &mut self.cells {
-            cell.locked_in = false;
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            if self.layout.as_ref().is_none_or(|l| l.is_active(index)) {
+                cell.locked_in = false;
+            }
+        }
+    }
+
+    // The number of cells in a row, which is wider than the size for a layout
+    pub fn width(&self) -> usize {
+        self.layout.as_ref().map_or(self.size, |l| l.width())
+    }
+
+    // The number of rows of cells, which is taller than the size for a layout
+    pub fn height(&self) -> usize {
+        self.cells.len() / self.width().max(1)
+    }
+
+    // Checks that the clues of every rule fit the grid, and that every rule works with the layout
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(rule) = self.unsupported_by_layout() {
+            return Err(format!("{} does not work with a layout", rule.get_name()));
         }
+        let (width, height) = (self.width(), self.height());
+        self.rules
+            .iter()
+            .try_for_each(|rule| rule.validate(self.size, width, height))
     }
+
+    // Returns the first rule that can not be used on the canvas of the layout
+    fn unsupported_by_layout(&self) -> Option<&DynRule> {
+        self.layout.as_ref()?;
+        self.rules.iter().find(|rule| !rule.supports_layout())
+    }
+
+    // Returns false for the cells outside the grids of a layout
+    pub fn is_active(&self, index: usize) -> bool {
+        self.layout.as_ref().is_none_or(|l| l.is_active(index))
+    }
+
+    // Writes the sudoku in the PSF, which parses back into the same sudoku.
+    // The header has the rules with all their clues, and the grid has the locked digits with 0 for the other cells.
+    // Digits only found by removing candidates are left out, so the PSF of a parsed sudoku is the PSF it was parsed from.
+    pub fn to_psf(&self) -> String {
+        let header: Vec<String> = self.rules.iter().filter_map(|r| r.to_psf()).collect();
+        let mut psf = self.metadata.to_psf();
+        // Without any rules to write the header is left out, since an empty rule name does not parse
+        if !header.is_empty() {
+            psf.push_str(&header.join(" | "));
+            psf.push_str("\n\n");
+        }
+
+        let width = self.width();
+        let alphabet = self.alphabet();
+        for (index, given) in self.givens().iter().enumerate() {
+            if index != 0 {
+                psf.push(',');
+                if index % width == 0 {
+                    psf.push('\n');
+                }
+            }
+            psf.push_str(&alphabet.write_digit(*given));
+        }
+        psf
+    }
+
+    // The alphabet PSF and Display write the digits in, which is the decimal numbers unless the sudoku has chosen one
+    // with a symbol for every digit
+    pub fn alphabet(&self) -> Alphabet {
+        self.metadata
+            .alphabet
+            .filter(|alphabet| alphabet.max_size() >= self.size)
+            .unwrap_or(Alphabet::Decimal)
+    }
+
+    // Returns the locked digit of every cell, or 0 for the cells that are not locked
+    pub fn givens(&self) -> Vec<u16> {
+        self.cells
+            .iter()
+            .map(|cell| match cell.available.as_slice() {
+                [value] if cell.locked_in => *value,
+                _ => 0,
+            })
+            .collect()
+    }
+
     // Author Thor s224817
     // Setting a cell outside solving context.
     pub fn set_cell(&mut self, n: u16, index: usize) -> Result<(), SudokuSolveError> {
@@ -276,6 +402,17 @@ impl Sudoku {
         ctx: Option<&AllSolutionsContext>,
         pri_queue: Option<PriorityQueue<usize, Entropy>>,
         new_states: Option<HashSet<u64>>,
+    ) -> Result<(), SudokuSolveError> {
+        self.solve_with_branch_limit(ctx, pri_queue, new_states, None)
+    }
+
+    // Solve, but give up with BranchLimitReached after branch_limit guesses.
+    fn solve_with_branch_limit(
+        &mut self,
+        ctx: Option<&AllSolutionsContext>,
+        pri_queue: Option<PriorityQueue<usize, Entropy>>,
+        new_states: Option<HashSet<u64>>,
+        branch_limit: Option<usize>,
     ) -> Result<(), SudokuSolveError> {
         let has_square = self
             .rules
@@ -301,6 +438,7 @@ impl Sudoku {
         let mut big_buffer = vec![];
         let mut arena = Self::get_arena();
         let mut state_buffer = Vec::with_capacity(self.cells.len());
+        let mut branch_count = 0;
 
         //If no states are given, create new hashset.
         let mut new_states = if let Some(new_states) = new_states {
@@ -388,8 +526,10 @@ impl Sudoku {
                                         return Err(SudokuSolveError::UnsolveableError);
                                     };
 
+                                    // The rest of the removals belong to the abandoned branch
                                     self.cells = cells;
                                     pri_queue = new_pri_queue;
+                                    continue 'main;
                                 }
                                 pri_queue.change_priority(
                                     remove_index,
@@ -418,8 +558,10 @@ impl Sudoku {
                                         return Err(SudokuSolveError::UnsolveableError);
                                     };
 
+                                    // The rest of the removals belong to the abandoned branch
                                     self.cells = cells;
                                     pri_queue = new_pri_queue;
+                                    continue 'main;
                                 }
                                 pri_queue.change_priority(
                                     index,
@@ -464,6 +606,14 @@ impl Sudoku {
 
                     // Analysis failed. Branch instead by chossing random number from popped cell.
 
+                    if let Some(branch_limit) = branch_limit {
+                        if branch_count >= branch_limit {
+                            Self::free_arena(arena);
+                            return Err(SudokuSolveError::BranchLimitReached);
+                        }
+                        branch_count += 1;
+                    }
+
                     let choice = random::<usize>() % entropy.0;
 
                     let n = self.cells[index].available[choice];
@@ -548,20 +698,44 @@ impl Sudoku {
         progess: Option<Box<dyn Fn(usize)>>,
         difficulty: Difficulty,
     ) -> Result<(Self, Self), SudokuSolveError> {
+        if let Some(layout) = rules.iter().find_map(|r| r.layout()) {
+            if layout.size != size {
+                return Err(SudokuSolveError::LayoutSizeMismatch(layout.size));
+            }
+        }
         let mut sudoku = Sudoku::new(size, rules);
+        if let Some(rule) = sudoku.unsupported_by_layout() {
+            return Err(SudokuSolveError::UnsupportedByLayout(rule.get_name()));
+        }
 
-        //Initial solve.
-        sudoku.solve(None, None, None)?;
+        //Initial solve. An unlucky early guess can make the search on an empty grid take forever
+        //with strict global rules like NonConsecutiveRule, so restart with a new limit when it guesses too much.
+        let mut attempt_count = 0;
+        sudoku = loop {
+            attempt_count += 1;
+            let branch_limit = luby(attempt_count) * (sudoku.cells.len() / 4).max(1);
+            let mut attempt = sudoku.clone();
+            match attempt.solve_with_branch_limit(None, None, None, Some(branch_limit)) {
+                Ok(()) => break attempt,
+                Err(SudokuSolveError::BranchLimitReached) => {}
+                Err(err) => return Err(err),
+            }
+        };
         sudoku.reset_locked();
         let solved = sudoku.clone();
         let mut extra_clues = 0;
 
+        let width = sudoku.width();
         for rule in &mut sudoku.rules {
-            rule.create_clue(&sudoku.cells, size);
+            rule.create_clue(&sudoku.cells, size, width);
             extra_clues += rule.no_of_clues();
         }
 
-        let remove_limit = difficulty.get_removes(size, extra_clues);
+        // A layout has more cells than a single grid, so the removes are scaled to the cells in use
+        let active_count = (0..sudoku.cells.len())
+            .filter(|index| sudoku.is_active(*index))
+            .count();
+        let remove_limit = difficulty.get_removes(size, extra_clues) * active_count / (size * size);
 
         const ATTEMPT_COUNT: usize = 25;
 
@@ -569,7 +743,9 @@ impl Sudoku {
 
         let mut count = 0;
         let mut currents_left = ATTEMPT_COUNT;
-        let mut available_to_remove: Vec<_> = (0..sudoku.cells.len()).collect();
+        let mut available_to_remove: Vec<_> = (0..sudoku.cells.len())
+            .filter(|index| sudoku.is_active(*index))
+            .collect();
 
         let mut good_cache = HashSet::<u64>::new();
         let mut bad_cache = HashSet::<u64>::new();
@@ -633,7 +809,18 @@ impl Sudoku {
         #[cfg(debug_assertions)]
         println!("Removed {count} in {:?}", timer.elapsed());
 
-        Ok((sudoku, solved))
+        // The digits left are the givens. They are placed on a new sudoku the way the givens of a parsed sudoku are,
+        // so the generated sudoku is the same as the one its PSF parses into
+        let mut puzzle = Sudoku::new(size, sudoku.rules.iter().map(|r| r.boxed_clone()).collect());
+        for (index, cell) in sudoku.cells.iter().enumerate() {
+            if let [value] = cell.available.as_slice() {
+                if puzzle.is_active(index) {
+                    puzzle.set_cell(*value, index)?;
+                }
+            }
+        }
+
+        Ok((puzzle, solved))
     }
     // Author Thor s224817
     fn cell_fastclone(&self) -> Vec<Cell> {
@@ -670,18 +857,79 @@ impl Sudoku {
     }
 }
 
+// The i'th number (from 1) in the Luby sequence 1,1,2,1,1,2,4,1,1,2,1,1,2,4,8,...
+// Used to size the restarts of a search, so most restarts are short but every size is tried eventually.
+fn luby(i: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if (1 << k) - 1 == i {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
 // Author Thor s224817
 #[derive(Debug, Clone)]
-pub enum ParseSudokuError {
+pub enum ParseErrorKind {
     ParseIntError(ParseIntError),
     InvalidSizeError(usize),
     UnsolveableError,
     InvalidRuleName(String),
+    InvalidMetadata(String),
+    // A digit that is not in the alphabet of the grid
+    InvalidDigit(String),
+    // A given larger than the size, like 10 in a 9x9
+    DigitOutOfRange(u16),
+    // The grid can be read in more than one alphabet, so it must be chosen with the alphabet metadata
+    AmbiguousAlphabet,
+    // A clue of a rule that does not fit the grid, like a cell outside it or a domino of cells that are not next to each other
+    InvalidClue(String),
+    // A rule that looks at the rows, columns or boxes of a single grid, next to a layout
+    UnsupportedByLayout(String),
+}
+
+// The error of the PSF parser, with the line and column (from 1) of the token it was found at.
+// The errors of the whole grid, like its size, point at the start of the grid with an empty token.
+#[derive(Debug, Clone)]
+pub struct ParseSudokuError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+}
+
+impl ParseSudokuError {
+    // The token must be a slice of the source, so its position can be found from where it starts
+    fn at(source: &str, token: &str, kind: ParseErrorKind) -> Self {
+        let offset = (token.as_ptr() as usize)
+            .saturating_sub(source.as_ptr() as usize)
+            .min(source.len());
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            kind,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            token: token.to_owned(),
+        }
+    }
 }
+
 // Author Thor s224817
 impl Display for ParseSudokuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        write!(
+            f,
+            "{:?} at line {}, column {}",
+            self.kind, self.line, self.column
+        )?;
+        if !self.token.is_empty() {
+            write!(f, ": {}", self.token)?;
+        }
+        Ok(())
     }
 }
 // Author Thor s224817 and Katinka s224805
@@ -689,8 +937,22 @@ impl Display for ParseSudokuError {
 impl FromStr for Sudoku {
     type Err = ParseSudokuError;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let error = |token: &str, kind| ParseSudokuError::at(source, token, kind);
         let mut rules: Vec<DynRule> = vec![];
+        // The text of every rule, to point at the rule when its clues do not fit the grid
+        let mut rule_tokens: Vec<&str> = vec![];
+
+        // The metadata is the lines starting with # before the rules
+        let mut metadata = Metadata::default();
+        let mut s = source.trim_start();
+        while s.starts_with('#') {
+            let (line, rest) = s.split_once('\n').unwrap_or((s, ""));
+            metadata
+                .read_line(line)
+                .map_err(|e| error(line.trim_end(), ParseErrorKind::InvalidMetadata(e)))?;
+            s = rest.trim_start();
+        }
 
         //Windows sucks. Split on all double newlines. Use regex because windows is edgy.
         let sudoku_source = match regex!(r"(\r\n|\n)(\r\n|\n)")
@@ -703,19 +965,71 @@ impl FromStr for Sudoku {
                     rules.push(
                         rule_name
                             .parse()
-                            .map_err(|e| ParseSudokuError::InvalidRuleName(e))?,
+                            .map_err(|e| error(rule_name, ParseErrorKind::InvalidRuleName(e)))?,
                     );
+                    rule_tokens.push(rule_name);
                 }
 
                 sudoku
             }
             _ => s,
         };
-
-        let size = sudoku_source.split(',').count().integer_sqrt();
+        let grid_start = &sudoku_source[..0];
+
+        let tokens: Vec<&str> = sudoku_source.split(',').map(str::trim).collect();
+        let cell_count = tokens.len();
+        let (size, width, height) = match rules.iter().find_map(|r| r.layout()) {
+            Some(layout) => {
+                if layout.width() * layout.height() != cell_count {
+                    return Err(error(
+                        grid_start,
+                        ParseErrorKind::InvalidSizeError(cell_count),
+                    ));
+                }
+                (layout.size, layout.width(), layout.height())
+            }
+            None => {
+                let size = cell_count.integer_sqrt();
+                if size * size != cell_count {
+                    return Err(error(
+                        grid_start,
+                        ParseErrorKind::InvalidSizeError(cell_count),
+                    ));
+                }
+                (size, size, size)
+            }
+        };
         let sub_size = size.integer_sqrt();
         if sub_size * sub_size != size {
-            return Err(ParseSudokuError::InvalidSizeError(size));
+            return Err(error(grid_start, ParseErrorKind::InvalidSizeError(size)));
+        }
+
+        // The alphabet is found from the digits, unless the metadata chose it
+        let alphabet = match metadata.alphabet {
+            Some(alphabet) => alphabet,
+            None => Alphabet::detect(tokens.iter().copied())
+                .ok_or_else(|| error(grid_start, ParseErrorKind::AmbiguousAlphabet))?,
+        };
+        if alphabet != Alphabet::Decimal {
+            metadata.alphabet = Some(alphabet);
+        }
+        if size > alphabet.max_size() {
+            return Err(error(grid_start, ParseErrorKind::InvalidSizeError(size)));
+        }
+
+        // The clues are checked before the solver sees them, as a cell outside the grid would panic while solving
+        let has_layout = rules.iter().any(|r| r.layout().is_some());
+        for (rule, token) in rules.iter_mut().zip(&rule_tokens) {
+            if has_layout && !rule.supports_layout() {
+                return Err(error(
+                    token,
+                    ParseErrorKind::UnsupportedByLayout(rule.get_name().to_owned()),
+                ));
+            }
+            // The clues are checked before set_size, which fills or cuts the clues of some rules to the size
+            rule.validate(size, width, height)
+                .map_err(|e| error(token, ParseErrorKind::InvalidClue(e)))?;
+            rule.set_size(size);
         }
 
         #[cfg(debug_assertions)]
@@ -723,35 +1037,151 @@ impl FromStr for Sudoku {
 
         let mut sudoku = Sudoku::new(size, rules);
 
-        for (index, part) in sudoku_source.split(',').map(str::trim).enumerate() {
-            let n = part
-                .parse()
-                .map_err(|e| ParseSudokuError::ParseIntError(e))?;
-            if n != 0 {
+        for (index, part) in tokens.into_iter().enumerate() {
+            let n = match alphabet {
+                Alphabet::Decimal => part
+                    .parse()
+                    .map_err(|e| error(part, ParseErrorKind::ParseIntError(e)))?,
+                _ => alphabet
+                    .read_digit(part)
+                    .ok_or_else(|| error(part, ParseErrorKind::InvalidDigit(part.to_owned())))?,
+            };
+            if n as usize > size {
+                return Err(error(part, ParseErrorKind::DigitOutOfRange(n)));
+            }
+            // The cells outside the grids of a layout are written as 0
+            if n != 0 && sudoku.is_active(index) {
                 sudoku
                     .set_cell(n, index)
-                    .map_err(|_| ParseSudokuError::UnsolveableError)?;
+                    .map_err(|_| error(part, ParseErrorKind::UnsolveableError))?;
             }
         }
 
+        if let Some(solution) = &metadata.solution {
+            if solution.len() != sudoku.cells.len() {
+                return Err(error(
+                    grid_start,
+                    ParseErrorKind::InvalidMetadata(format!(
+                        "The solution has {} digits for {} cells",
+                        solution.len(),
+                        sudoku.cells.len()
+                    )),
+                ));
+            }
+        }
+        sudoku.metadata = metadata;
+
         Ok(sudoku)
     }
 }
 // Author Katinka s224805
 impl Display for Sudoku {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let alphabet = self.alphabet();
         for (index, cell) in self.cells.iter().enumerate() {
-            if index % self.size == 0 {
+            if index % self.width() == 0 {
                 f.write_char('\n')?;
             }
-            write!(f, "{:?},", cell.available)?;
+            match alphabet {
+                Alphabet::Decimal => write!(f, "{:?},", cell.available)?,
+                _ => {
+                    let digits: Vec<String> = cell
+                        .available
+                        .iter()
+                        .map(|d| alphabet.write_digit(*d))
+                        .collect();
+                    write!(f, "[{}],", digits.join(", "))?
+                }
+            }
         }
 
         Ok(())
     }
 }
+
+// The sudoku is stored as its size, cells and rules. The rest is found again by Sudoku::new when it is read.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Sudoku {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Sudoku", 4)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("cells", &self.cells)?;
+        state.serialize_field("rules", &self.rules)?;
+        if self.metadata.is_empty() {
+            state.skip_field("metadata")?;
+        } else {
+            state.serialize_field("metadata", &self.metadata)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Sudoku {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        struct SudokuData {
+            size: usize,
+            cells: Vec<Cell>,
+            rules: Vec<DynRule>,
+            #[serde(default)]
+            metadata: Metadata,
+        }
+
+        let data = SudokuData::deserialize(deserializer)?;
+        if data.size.integer_sqrt().pow(2) != data.size {
+            return Err(D::Error::custom(format!("Invalid size {}", data.size)));
+        }
+        if let Some(layout) = data.rules.iter().find_map(|r| r.layout()) {
+            if layout.size != data.size {
+                return Err(D::Error::custom(format!(
+                    "The layout has size {} in a sudoku of size {}",
+                    layout.size, data.size
+                )));
+            }
+        }
+
+        let mut sudoku = Sudoku::new(data.size, data.rules);
+        if data.cells.len() != sudoku.cells.len() {
+            return Err(D::Error::custom(format!(
+                "Expected {} cells, found {}",
+                sudoku.cells.len(),
+                data.cells.len()
+            )));
+        }
+        sudoku.validate().map_err(D::Error::custom)?;
+        // The candidates must be digits of the sudoku, and the cells outside the grids of a layout stay 0
+        for (index, cell) in data.cells.iter().enumerate() {
+            let valid = if sudoku.is_active(index) {
+                !cell.available.is_empty()
+                    && cell
+                        .available
+                        .iter()
+                        .all(|value| (1..=data.size as u16).contains(value))
+            } else {
+                cell.is_single_eq(0)
+            };
+            if !valid {
+                return Err(D::Error::custom(format!(
+                    "The cell {index} has the candidates {:?} in a sudoku of size {}",
+                    cell.available.as_slice(),
+                    data.size
+                )));
+            }
+        }
+        sudoku.cells = data.cells;
+        sudoku.metadata = data.metadata;
+        Ok(sudoku)
+    }
+}
+
 // Author Thor s224817 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     pub available: smallvec::SmallVec<[u16; 16]>,
     pub locked_in: bool,
@@ -791,6 +1221,8 @@ impl Clone for Sudoku {
             cells: self.cell_fastclone(),
             rules: self.rules.iter().map(|r| r.boxed_clone()).collect(),
             has_square: self.has_square,
+            layout: self.layout.clone(),
+            metadata: self.metadata.clone(),
         }
     }
 }
@@ -860,37 +1292,20 @@ fn generate_4x4_xdiagonal() {
 // Author Thor s224817
 #[test]
 fn solve_test() {
-    use std::collections::HashMap;
     use std::fs;
 
     let sudokus = fs::read_dir("./tests").unwrap();
 
-    let mut solutions = HashMap::<String, String>::new();
-
     for file in sudokus.map(Result::unwrap) {
         let filename = file.file_name().to_string_lossy().to_string();
-        let sudoku_name;
 
         let mut sudoku: Sudoku = fs::read_to_string(file.path()).unwrap().parse().unwrap();
+        let solution = sudoku.metadata.solution.clone().unwrap();
 
-        if filename.contains("Løsning") {
-            sudoku_name = filename.split_whitespace().next().unwrap().to_string();
-        } else {
-            sudoku.solve(None, None, None).unwrap();
-            sudoku_name = filename;
-        }
-
-        let solution = sudoku.to_string();
-        if let Some(other_solution) = solutions.get(&sudoku_name) {
-            assert_eq!(solution, *other_solution);
-            println!("{sudoku_name} solved correctly");
-        } else {
-            solutions.insert(sudoku_name, solution);
-        }
-    }
+        sudoku.solve(None, None, None).unwrap();
 
-    for (key, value) in solutions {
-        println!("{key}: {value}");
+        assert_eq!(sudoku.givens(), solution);
+        println!("{filename} solved correctly");
     }
 }
 // Author Katinka s224805
@@ -1012,7 +1427,7 @@ fn generate_thermometer_sudoku() {
         9,
         vec![
             super::rules::square_rule::SquareRule::new(),
-            crate::rules::thermometer_rule::ThermometerRule::new(vec![]),
+            crate::rules::thermometer_rule::ThermometerRule::new(vec![], vec![]),
         ],
         None,
         Difficulty::Expert,
@@ -1095,6 +1510,590 @@ fn generate_sudoku_consecutive() {
 
     println!("{sudoku} at {:?}", timer.elapsed());
 }
+
+// Generates a sudoku with the rules, and checks that its clues survive being written to and read from the PSF.
+// Each generate test below checks the clues against the solution, and its own clue counts.
+#[cfg(test)]
+fn generate_round_trip(size: usize, rules: Vec<DynRule>) -> (Sudoku, Sudoku) {
+    let (sudoku, solved) = Sudoku::generate_with_size(size, rules, None, Difficulty::Easy)
+        .expect("Failed to generate sudoku");
+
+    let psf = sudoku.to_psf();
+    println!("{psf}");
+    let parsed: Sudoku = psf.parse().unwrap();
+    assert_eq!(parsed.to_psf(), psf);
+    for (rule, parsed_rule) in sudoku.rules.iter().zip(&parsed.rules) {
+        assert_eq!(rule.clues(), parsed_rule.clues());
+    }
+    (sudoku, solved)
+}
+
+#[test]
+fn generate_sudoku_non_consecutive() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::non_consecutive_rule::NonConsecutiveRule::new(),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_even_odd() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::even_odd_rule::EvenOddRule::new(vec![]),
+        ],
+    );
+    assert_eq!(
+        sudoku.rules.iter().map(|r| r.no_of_clues()).sum::<usize>(),
+        9
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_xv() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::sum_dot_rule::SumDotRule::xv(vec![], false),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_difference_dots() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::difference_dot_rule::DifferenceDotRule::new(vec![], vec![1, 2], false),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_between_lines() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::between_line_rule::BetweenLineRule::new(vec![]),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_region_sum_lines() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::region_sum_line_rule::RegionSumLineRule::new(vec![]),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_sudoku_quadruple() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::quadruple_rule::QuadrupleRule::new(vec![]),
+        ],
+    );
+    assert_eq!(
+        sudoku.rules.iter().map(|r| r.no_of_clues()).sum::<usize>(),
+        4
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_windoku() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::extra_regions_rule::ExtraRegionsRule::windoku(),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_skyscraper_sudoku() {
+    let (sudoku, solved) = generate_round_trip(
+        4,
+        vec![crate::rules::skyscraper_rule::SkyscraperRule::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        )],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_inequality_sudoku() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::inequality_rule::InequalityRule::new(vec![]),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_constraint_sudoku() {
+    let (sudoku, solved) = generate_round_trip(
+        9,
+        vec![
+            super::rules::square_rule::SquareRule::new(),
+            crate::rules::constraint_rule::ConstraintRule::new(vec![]),
+        ],
+    );
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+}
+
+#[test]
+fn generate_samurai_sudoku() {
+    use crate::rules::layout_rule::{Layout, LayoutRule};
+
+    let (sudoku, solved) = Sudoku::generate_with_size(
+        9,
+        vec![LayoutRule::new(Layout::samurai(9))],
+        None,
+        Difficulty::Easy,
+    )
+    .expect("Failed to generate sudoku");
+
+    assert_eq!(sudoku.cells.len(), 21 * 21);
+    assert!(sudoku.cells[9].is_single_eq(0));
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+
+    let mut unique = sudoku.clone();
+    unique.solve(None, None, None).unwrap();
+    assert!(unique
+        .cells
+        .iter()
+        .zip(&solved.cells)
+        .all(|(cell, solved_cell)| cell.available == solved_cell.available));
+    println!("{sudoku}");
+}
+
+#[test]
+fn solve_twodoku() {
+    let mut sudoku: Sudoku = "LayoutRule ; 4 ; twodoku\n\n\
+        1,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0"
+        .parse()
+        .unwrap();
+    assert_eq!(sudoku.width(), 6);
+    assert!(!sudoku.is_active(4));
+
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&sudoku)));
+    assert!(sudoku.cells[4].is_single_eq(0));
+}
+
+#[test]
+fn generate_layout_rules() {
+    use crate::rules::{
+        between_line_rule::BetweenLineRule,
+        clue::Clue,
+        consecutive_rule::ConsecutiveRule,
+        constraint_rule::ConstraintRule,
+        difference_dot_rule::DifferenceDotRule,
+        even_odd_rule::EvenOddRule,
+        inequality_rule::InequalityRule,
+        layout_rule::{Layout, LayoutRule},
+        parity_rule::ParityRule,
+        quadruple_rule::QuadrupleRule,
+        sum_dot_rule::SumDotRule,
+        x_rule::XRule,
+    };
+
+    let cases: Vec<DynRule> = vec![
+        XRule::new(vec![]),
+        ParityRule::new(vec![]),
+        ConsecutiveRule::new(vec![]),
+        EvenOddRule::new(vec![]),
+        SumDotRule::xv(vec![], true),
+        DifferenceDotRule::new(vec![], vec![1], false),
+        InequalityRule::new(vec![]),
+        BetweenLineRule::new(vec![]),
+        ConstraintRule::new(vec![]),
+        QuadrupleRule::new(vec![]),
+    ];
+
+    for rule in cases {
+        let (sudoku, solved) = Sudoku::generate_with_size(
+            4,
+            vec![LayoutRule::new(Layout::twodoku(4)), rule],
+            None,
+            Difficulty::Easy,
+        )
+        .expect("Failed to generate sudoku");
+        println!("{}", sudoku.to_psf());
+
+        assert!(sudoku.validate().is_ok());
+        assert!(sudoku.rules.iter().all(|r| r.finished_legal(&solved)));
+        // no clue reaches the cells outside the two grids
+        let clue_cells: Vec<usize> = sudoku
+            .rules
+            .iter()
+            .flat_map(|r| r.clues())
+            .flat_map(|clue| match clue {
+                Clue::Domino { cells, .. } => vec![cells.0, cells.1],
+                Clue::Line { cells, .. } | Clue::Cage { cells, .. } => cells,
+                Clue::Cell { index, .. } => vec![index],
+                Clue::Corner { index, .. } => vec![index, index + 1, index + 6, index + 7],
+                _ => vec![],
+            })
+            .collect();
+        assert!(clue_cells.iter().all(|index| sudoku.is_active(*index)));
+
+        let parsed: Sudoku = sudoku.to_psf().parse().unwrap();
+        assert_eq!(parsed.to_psf(), sudoku.to_psf());
+    }
+}
+
+#[test]
+fn layout_unsupported_rules() {
+    use crate::rules::layout_rule::{Layout, LayoutRule};
+
+    let grid = "\n\n1,2,0,0,0,0,\n\
+        3,4,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0,\n\
+        0,0,0,0,0,0";
+
+    // the circle covers the cells 0, 1, 6 and 7 of the canvas, which is 6 cells wide
+    let mut sudoku: Sudoku = format!("QuadrupleRule ; 0,1,2,3,4 | LayoutRule ; 4 ; twodoku{grid}")
+        .parse()
+        .unwrap();
+    sudoku.solve(None, None, None).unwrap();
+    assert!(sudoku.rules.iter().all(|r| r.finished_legal(&sudoku)));
+
+    for rule in ["SquareRule", "NonConsecutiveRule", "KnightsMove"] {
+        let err = format!("{rule} | LayoutRule ; 4 ; twodoku{grid}")
+            .parse::<Sudoku>()
+            .unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnsupportedByLayout(_)));
+        assert_eq!(err.token, rule);
+    }
+
+    assert!(matches!(
+        Sudoku::generate_with_size(
+            4,
+            vec![LayoutRule::new(Layout::twodoku(4)), SquareRule::new()],
+            None,
+            Difficulty::Easy,
+        ),
+        Err(SudokuSolveError::UnsupportedByLayout("SquareRule"))
+    ));
+    // a layout of another size than the sudoku is an error, not a panic
+    assert!(matches!(
+        Sudoku::generate_with_size(
+            4,
+            vec![LayoutRule::new(Layout::twodoku(9))],
+            None,
+            Difficulty::Easy,
+        ),
+        Err(SudokuSolveError::LayoutSizeMismatch(9))
+    ));
+}
+
+#[test]
+fn psf_round_trip() {
+    let psf = "XRule ;0,1 | ZipperRule ;5,4+6 | ThermometerRule ;8,12 ; slow 9,13 | ParityRule ; 2,3 | ConsecutiveRule ; 10,11 | SquareRule\n\n\
+        1,2,3,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0";
+    let sudoku: Sudoku = psf.parse().unwrap();
+
+    // the 4 in the first row is only found by removing candidates, so it is not a given
+    assert!(sudoku.cells[3].is_single_eq(4));
+    assert_eq!(sudoku.to_psf(), psf);
+
+    let no_rules: Sudoku = "1,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0".parse().unwrap();
+    assert_eq!(no_rules.to_psf(), "1,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0");
+}
+
+#[test]
+fn generated_psf_round_trip() {
+    use crate::rules::{
+        consecutive_rule::ConsecutiveRule, parity_rule::ParityRule,
+        thermometer_rule::ThermometerRule, x_rule::XRule, zipper_rule::ZipperRule,
+    };
+
+    let (sudoku, _) = Sudoku::generate_with_size(
+        9,
+        vec![
+            SquareRule::new(),
+            XRule::new(vec![]),
+            ZipperRule::new(vec![]),
+            ThermometerRule::new(vec![], vec![]),
+            ParityRule::new(vec![]),
+            ConsecutiveRule::new(vec![]),
+        ],
+        None,
+        Difficulty::Easy,
+    )
+    .expect("Failed to generate sudoku");
+
+    let psf = sudoku.to_psf();
+    println!("{psf}");
+    let parsed: Sudoku = psf.parse().unwrap();
+    assert_eq!(parsed.to_psf(), psf);
+    for (rule, parsed_rule) in sudoku.rules.iter().zip(&parsed.rules) {
+        assert_eq!(rule.clues(), parsed_rule.clues());
+    }
+    for (cell, parsed_cell) in sudoku.cells.iter().zip(&parsed.cells) {
+        if cell.locked_in {
+            assert_eq!(cell.available, parsed_cell.available);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let mut sudoku: Sudoku =
+        "# title: Serde\nXRule ;0,1 | ThermometerRule ;8,12 ; slow 9,13 | SquareRule\n\n\
+        1,2,3,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0,\n\
+        0,0,0,0"
+            .parse()
+            .unwrap();
+
+    let json = serde_json::to_string(&sudoku).unwrap();
+    let parsed: Sudoku = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.cells, sudoku.cells);
+    assert_eq!(parsed.to_psf(), sudoku.to_psf());
+    assert_eq!(parsed.metadata.title.as_deref(), Some("Serde"));
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+
+    // a sudoku in the middle of being solved keeps its candidates
+    sudoku.solve(None, None, None).unwrap();
+    let parsed: Sudoku = serde_json::from_str(&serde_json::to_string(&sudoku).unwrap()).unwrap();
+    assert_eq!(parsed.cells, sudoku.cells);
+
+    let difficulty: Difficulty = serde_json::from_str(r#""Hard""#).unwrap();
+    assert!(matches!(difficulty, Difficulty::Hard));
+
+    assert!(serde_json::from_str::<Sudoku>(r#"{"size":4,"cells":[],"rules":[]}"#).is_err());
+
+    // candidates outside the digits of the sudoku are not loaded
+    for candidates in [
+        serde_json::json!([5]),
+        serde_json::json!([0]),
+        serde_json::json!([]),
+    ] {
+        let mut value = serde_json::to_value(&sudoku).unwrap();
+        value["cells"][5]["available"] = candidates;
+        assert!(serde_json::from_value::<Sudoku>(value).is_err());
+    }
+
+    // the cells outside the grids of a layout must stay 0
+    let twodoku: Sudoku = format!("LayoutRule ; 4 ; twodoku\n\n{}", ["0"; 36].join(","))
+        .parse()
+        .unwrap();
+    let mut value = serde_json::to_value(&twodoku).unwrap();
+    assert!(serde_json::from_value::<Sudoku>(value.clone()).is_ok());
+    value["cells"][4]["available"] = serde_json::json!([1]);
+    assert!(serde_json::from_value::<Sudoku>(value).is_err());
+}
+
+#[test]
+fn psf_metadata_test() {
+    let psf = "# title: Small\n\
+        # author: Thor\n\
+        # rating: Easy\n\
+        # description: Normal sudoku rules apply\n\
+        # solution: 1,2,3,4,3,4,1,2,2,1,4,3,4,3,2,1\n\
+        SquareRule\n\n\
+        1,0,0,0,\n\
+        0,4,0,0,\n\
+        0,0,4,0,\n\
+        0,3,0,1";
+    let mut sudoku: Sudoku = psf.parse().unwrap();
+    assert_eq!(sudoku.metadata.author.as_deref(), Some("Thor"));
+    assert!(matches!(
+        sudoku.metadata.difficulty(),
+        Some(Difficulty::Easy)
+    ));
+    assert_eq!(sudoku.to_psf(), psf);
+
+    let solution = sudoku.metadata.solution.clone().unwrap();
+    sudoku.solve(None, None, None).unwrap();
+    assert_eq!(sudoku.givens(), solution);
+
+    // the metadata is kept without any rules too
+    let ruleless: Sudoku = "# title: Ruleless\n1,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        ruleless.to_psf(),
+        "# title: Ruleless\n1,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0"
+    );
+
+    assert!(matches!(
+        psf.replace("1,2,3,4,3", "1,2,3").parse::<Sudoku>(),
+        Err(ParseSudokuError {
+            kind: ParseErrorKind::InvalidMetadata(_),
+            ..
+        })
+    ));
+    assert!(matches!(
+        "# title\nSquareRule\n\n0,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0".parse::<Sudoku>(),
+        Err(ParseSudokuError {
+            kind: ParseErrorKind::InvalidMetadata(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn psf_alphabet_test() {
+    let mut grid = vec!["."; 256];
+    grid[0] = "A";
+    grid[17] = "P";
+    let psf = format!("SquareRule\n\n{}", grid.join(","));
+
+    let sudoku: Sudoku = psf.parse().unwrap();
+    assert_eq!(sudoku.alphabet(), Alphabet::Letters);
+    assert!(sudoku.cells[0].is_single_eq(1));
+    assert!(sudoku.cells[17].is_single_eq(16));
+    assert!(sudoku.to_string().starts_with("\n[A],"));
+
+    let written = sudoku.to_psf();
+    assert!(written.starts_with("# alphabet: letters\nSquareRule\n\nA,.,.,"));
+    let parsed: Sudoku = written.parse().unwrap();
+    assert_eq!(parsed.givens(), sudoku.givens());
+
+    // only A to F can be either alphabet, so it must be chosen
+    let ambiguous = psf.replace('P', "F");
+    assert!(matches!(
+        ambiguous.parse::<Sudoku>(),
+        Err(ParseSudokuError {
+            kind: ParseErrorKind::AmbiguousAlphabet,
+            ..
+        })
+    ));
+    let hex: Sudoku = format!("# alphabet: hex\n{ambiguous}").parse().unwrap();
+    assert!(hex.cells[0].is_single_eq(11));
+    assert!(hex.cells[17].is_single_eq(16));
+
+    assert!(matches!(
+        psf.replace('P', "Z").parse::<Sudoku>(),
+        Err(ParseSudokuError {
+            kind: ParseErrorKind::InvalidDigit(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn psf_error_position_test() {
+    let error = "XRule ;0,1 | SquareRule\n\n0,0,0,0,\n0,0,-1,0,\n0,0,0,0,\n0,0,0,0"
+        .parse::<Sudoku>()
+        .unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::ParseIntError(_)));
+    assert_eq!((error.line, error.column), (4, 5));
+    assert_eq!(error.token, "-1");
+
+    let error = "# title: Errors\nSquareRule | NoRule\n\n0"
+        .parse::<Sudoku>()
+        .unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::InvalidRuleName(_)));
+    assert_eq!((error.line, error.column), (2, 14));
+    assert_eq!(
+        error.to_string(),
+        format!("{:?} at line 2, column 14: NoRule", error.kind)
+    );
+
+    let error = "SquareRule\n\n0,0,0,0,\n0,5,0,0,\n0,0,0,0,\n0,0,0,0"
+        .parse::<Sudoku>()
+        .unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::DigitOutOfRange(5)));
+    assert_eq!((error.line, error.column), (4, 3));
+}
+
+#[test]
+fn psf_clue_validation_test() {
+    let grid = vec!["0"; 81].join(",");
+    let parse = |rules: &str| format!("{rules}\n\n{grid}").parse::<Sudoku>();
+
+    assert!(parse("XRule ;0,1;1,10 | SquareRule").is_ok());
+    let error = parse("SquareRule | XRule ;200,201").unwrap_err();
+    assert!(matches!(error.kind, ParseErrorKind::InvalidClue(_)));
+    assert_eq!((error.line, error.column), (1, 14));
+    assert_eq!(error.token, "XRule ;200,201");
+
+    // the last cell of a row is not next to the first cell of the next row
+    assert!(matches!(
+        parse("XRule ;8,9").map_err(|e| e.kind),
+        Err(ParseErrorKind::InvalidClue(_))
+    ));
+    assert!(matches!(
+        parse("ThermometerRule ;0,1,2;20,40").map_err(|e| e.kind),
+        Err(ParseErrorKind::InvalidClue(_))
+    ));
+    assert!(parse("ThermometerRule ;0,1,2;20,30,40").is_ok());
+
+    // the digits of the clues have to be in the grid
+    assert!(parse("QuadrupleRule ; 0,9").is_ok());
+    assert!(matches!(
+        parse("QuadrupleRule ; 0,10").map_err(|e| e.kind),
+        Err(ParseErrorKind::InvalidClue(_))
+    ));
+    // the clues past the size are refused before set_size cuts them off
+    let skyscraper = |top: &str| {
+        format!("SkyscraperRule ; top {top} | SquareRule\n\n0,0,0,0,\n0,0,0,0,\n0,0,0,0,\n0,0,0,0")
+            .parse::<Sudoku>()
+    };
+    assert!(skyscraper("1,0,2,0").is_ok());
+    assert!(matches!(
+        skyscraper("1,0,2,0,3").map_err(|e| e.kind),
+        Err(ParseErrorKind::InvalidClue(_))
+    ));
+}
+
+#[test]
+fn psf_grid_size_test() {
+    // 83 cells are not a square, even though the square root rounds down to a valid size
+    for digit in ["0", "1"] {
+        let grid = vec![digit; 83].join(",");
+        assert!(matches!(
+            grid.parse::<Sudoku>().map_err(|e| e.kind),
+            Err(ParseErrorKind::InvalidSizeError(83))
+        ));
+    }
+}
+
 // Author Katinka s224805
 #[test]
 fn knights_xsudoku() {